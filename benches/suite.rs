@@ -0,0 +1,230 @@
+// Times parsing, formatting, cloning and dropping on generated documents shaped like the usual
+// JSON benchmark corpora, and prints the results as JSON:
+// cargo bench --bench suite [-- corpus names...] > results.json
+use microjson::{JsonValue, json, parse_borrowed};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const BUDGET: Duration = Duration::from_millis(500);
+const MIN_RUNS: usize = 5;
+
+type Generate = fn(&mut Rng) -> JsonValue;
+
+// Deterministic xorshift, so every run measures the same documents
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	fn below(&mut self, n: u32) -> u32 {
+		(self.next() % n as u64) as u32
+	}
+
+	fn float(&mut self) -> f64 {
+		self.next() as f64 / u64::MAX as f64
+	}
+
+	fn word(&mut self) -> String {
+		const SYLLABLES: [&str; 12] = [
+			"ka", "lo", "mi", "ne", "ru", "ta", "shi", "ve", "zo", "é", "ß", "日本",
+		];
+		let len = 1 + self.below(4);
+		(0..len)
+			.map(|_| SYLLABLES[self.below(12) as usize])
+			.collect()
+	}
+
+	fn sentence(&mut self, words: u32) -> String {
+		let words: Vec<_> = (0..1 + self.below(words)).map(|_| self.word()).collect();
+		words.join(" ")
+	}
+}
+
+// Statuses with nested users and entities: mostly strings, some of them escaped
+fn twitter(rng: &mut Rng) -> JsonValue {
+	let statuses: JsonValue = (0..500)
+		.map(|idx| {
+			let user = json!({
+				"id": rng.below(1 << 30),
+				"name": rng.sentence(2),
+				"screen_name": rng.word(),
+				"description": rng.sentence(20) + "\n\"quoted\"\t\\",
+				"followers_count": rng.below(100000),
+				"verified": rng.below(10) == 0,
+				"profile_image_url": format!("http://pbs.example.com/{}.png", rng.word()),
+				"url": null,
+			});
+			let hashtags: JsonValue = (0..rng.below(3))
+				.map(|_| json!({"text": rng.word(), "indices": [rng.below(140), rng.below(140)]}))
+				.collect();
+			json!({
+				"id": idx,
+				"text": rng.sentence(25),
+				"created_at": "Sun Aug 31 00:29:15 +0000 2014",
+				"user": user,
+				"entities": {"hashtags": hashtags, "urls": [], "user_mentions": []},
+				"retweet_count": rng.below(1000),
+				"favorited": false,
+				"in_reply_to_status_id": null,
+				"lang": "ja",
+			})
+		})
+		.collect();
+	json!({"statuses": statuses, "search_metadata": {"count": 500, "query": "一"}})
+}
+
+// A few polygons with long runs of coordinate pairs: almost entirely floats
+fn canada(rng: &mut Rng) -> JsonValue {
+	let polygons: JsonValue = (0..40)
+		.map(|_| {
+			let ring: JsonValue = (0..1200)
+				.map(|_| json!([-140. + 90. * rng.float(), 40. + 40. * rng.float()]))
+				.collect();
+			json!([ring])
+		})
+		.collect();
+	json!({
+		"type": "FeatureCollection",
+		"features": [{
+			"type": "Feature",
+			"properties": {"name": "Canada"},
+			"geometry": {"type": "Polygon", "coordinates": polygons},
+		}],
+	})
+}
+
+// Lookup tables keyed by id and lists of events full of small integers and nulls
+fn citm_catalog(rng: &mut Rng) -> JsonValue {
+	let mut events = JsonValue::object();
+	for idx in 0..1000 {
+		let event = json!({
+			"id": 138586341 + idx,
+			"name": rng.sentence(4),
+			"description": null,
+			"logo": format!("/images/UE0AAAAACEKo{idx}QAAAAVDSVRN"),
+			"subTopicIds": [337184269, 337184283, rng.below(1 << 30)],
+			"subjectCode": null,
+			"subtitle": null,
+			"topicIds": [324846099, 107888604],
+		});
+		events = events.with((138586341 + idx).to_string(), event);
+	}
+	let performances: JsonValue = (0..2000)
+		.map(|idx| {
+			let prices: JsonValue = (0..rng.below(8))
+				.map(|_| json!({"amount": rng.below(200) * 10, "audienceSubCategoryId": 337100890, "seatCategoryId": rng.below(1 << 30)}))
+				.collect();
+			json!({
+				"id": 339887544 + idx,
+				"eventId": 138586341 + rng.below(1000) as i32,
+				"logo": null,
+				"name": null,
+				"prices": prices,
+				"seatCategories": [{"areas": [{"areaId": 205705999, "blockIds": []}], "seatCategoryId": 338937295}],
+				"start": 1372701600000.,
+				"venueCode": "PLEYEL_PLEYEL",
+			})
+		})
+		.collect();
+	json!({"events": events, "performances": performances, "venueNames": {"PLEYEL_PLEYEL": "Salle Pleyel"}})
+}
+
+// Rows of short words, where allocating each string is most of the work
+fn short_strings(rng: &mut Rng) -> JsonValue {
+	(0..20000)
+		.map(|_| (0..8).map(|_| rng.word()).collect::<JsonValue>())
+		.collect()
+}
+
+fn deep() -> JsonValue {
+	("{\"deep\":".repeat(100000) + &"[".repeat(100000) + &"]".repeat(100000) + &"}".repeat(100000))
+		.parse()
+		.unwrap()
+}
+
+// Runs op on a fresh input from setup until the time budget is spent, timing only op
+fn measure<T, R>(
+	bytes: usize,
+	mut setup: impl FnMut() -> T,
+	mut op: impl FnMut(T) -> R,
+) -> JsonValue {
+	let mut times = vec![];
+	let started = Instant::now();
+	while times.len() < MIN_RUNS || started.elapsed() < BUDGET {
+		let input = setup();
+		let start = Instant::now();
+		let output = op(input);
+		times.push(start.elapsed());
+		// Anything op returns is dropped outside the timed region
+		drop(black_box(output));
+	}
+
+	times.sort();
+	let min = times[0];
+	let median = times[times.len() / 2];
+	json!({
+		"runs": times.len() as f64,
+		"min_ns": min.as_nanos() as f64,
+		"median_ns": median.as_nanos() as f64,
+		"mb_per_s": bytes as f64 / median.as_secs_f64() / 1e6,
+	})
+}
+
+fn main() {
+	// cargo bench passes --bench, and anything else is a corpus to run
+	let filters: Vec<String> = std::env::args()
+		.skip(1)
+		.filter(|arg| !arg.starts_with("--"))
+		.collect();
+
+	let mut rng = Rng(0x2545f4914f6cdd1d);
+	let corpora: [(&str, Generate); 5] = [
+		("twitter", twitter),
+		("canada", canada),
+		("citm_catalog", citm_catalog),
+		("short_strings", short_strings),
+		("deep", |_| deep()),
+	];
+
+	let mut results = vec![];
+	for (name, generate) in corpora {
+		let value = generate(&mut rng);
+		if !filters.is_empty() && !filters.iter().any(|filter| filter == name) {
+			continue;
+		}
+		let text = value.to_string();
+		let bytes = text.len();
+		eprintln!("{name}: {bytes} bytes");
+
+		let parse = measure(bytes, || (), |()| text.parse::<JsonValue>().unwrap());
+		// Borrows strings without escapes instead of copying them, so the gap to parse is what
+		// building owned values costs
+		let parse_borrowed = measure(bytes, || (), |()| parse_borrowed(&text).unwrap());
+		let display = measure(bytes, || (), |()| value.to_string());
+		// Pretty-printing indents every level, so its output grows with the square of the depth
+		let debug = match name {
+			"deep" => JsonValue::Null,
+			_ => measure(bytes, || (), |()| format!("{value:?}")),
+		};
+		let clone = measure(bytes, || (), |()| value.clone());
+		let drop = measure(bytes, || text.parse::<JsonValue>().unwrap(), std::mem::drop);
+		results.push(json!({
+			"name": name,
+			"bytes": bytes as f64,
+			"parse": parse,
+			"parse_borrowed": parse_borrowed,
+			"display": display,
+			"debug": debug,
+			"clone": clone,
+			"drop": drop,
+		}));
+	}
+
+	// Canonical output sorts the keys, so results from different runs diff cleanly
+	println!("{}", JsonValue::from(results).to_canonical_string());
+}
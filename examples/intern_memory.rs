@@ -0,0 +1,62 @@
+// Compares the heap used by a parsed array of records with and without key interning:
+// cargo run --release --example intern_memory
+use microjson::{JsonValue, KeyInterner, json};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		LIVE.fetch_add(layout.size(), Ordering::Relaxed);
+		// SAFETY: forwarded unchanged from our caller
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+		// SAFETY: forwarded unchanged from our caller
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn measure<T>(parse: impl FnOnce() -> T) -> (T, usize) {
+	let before = LIVE.load(Ordering::Relaxed);
+	let value = parse();
+	(value, LIVE.load(Ordering::Relaxed) - before)
+}
+
+fn main() {
+	let records: JsonValue = (0..100_000)
+		.map(|i| {
+			json!({
+				"id": i,
+				"username": format!("user{i}"),
+				"email_verified": i % 3 == 0,
+				"created_at": "2024-01-01T00:00:00Z",
+				"account_balance": i as f64 * 1.25,
+				"preferences": {"newsletter": true, "theme": "dark"},
+			})
+		})
+		.collect();
+	let input = records.to_string();
+	drop(records);
+
+	let (plain, plain_bytes) = measure(|| input.parse::<JsonValue>().unwrap());
+	let mut interner = KeyInterner::new();
+	let (interned, interned_bytes) = measure(|| interner.parse(&input).unwrap());
+	assert_eq!(interned.to_value(), plain);
+
+	println!("{} bytes of JSON, {} distinct keys", input.len(), interner.len());
+	println!("JsonValue:     {plain_bytes:>10} bytes");
+	println!("InternedValue: {interned_bytes:>10} bytes");
+	println!(
+		"saved {:.1}%",
+		100. * (1. - interned_bytes as f64 / plain_bytes as f64)
+	);
+}
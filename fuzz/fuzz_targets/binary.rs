@@ -0,0 +1,10 @@
+// Snapshots are read from untrusted bytes too, so neither reader may panic on them
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use microjson::{JsonValue, JsonView};
+
+fuzz_target!(|data: &[u8]| {
+	let _ = JsonValue::from_binary(data);
+	let _ = JsonView::new(data).map(|view| view.to_value());
+});
@@ -0,0 +1,25 @@
+// Every parser must return an error rather than panic, and whatever from_str accepts must
+// survive a round trip through the serializer
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use microjson::{JsonArena, JsonValue, parse_borrowed};
+
+fuzz_target!(|data: &[u8]| {
+	let Ok(input) = std::str::from_utf8(data) else {
+		return;
+	};
+	let _ = JsonValue::parse_fast(input);
+	let _ = JsonArena::new()
+		.parse(input)
+		.map(|value| value.into_owned());
+	let borrowed = parse_borrowed(input).ok().map(JsonValue::from);
+
+	let Ok(value) = input.parse::<JsonValue>() else {
+		assert!(borrowed.is_none());
+		return;
+	};
+	assert_eq!(borrowed.as_ref(), Some(&value));
+	assert_eq!(value.to_string().parse::<JsonValue>().as_ref(), Ok(&value));
+	assert_eq!(format!("{value:?}").parse::<JsonValue>(), Ok(value));
+});
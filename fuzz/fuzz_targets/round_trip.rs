@@ -0,0 +1,18 @@
+// parse(to_string(v)) == v for generated values, through every format that can hold them
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use microjson::JsonValue;
+use microjson::arbitrary::Generator;
+
+fuzz_target!(|data: &[u8]| {
+	let value = Generator::new(data).value();
+	let text = value.to_string();
+	assert_eq!(text.parse::<JsonValue>().as_ref(), Ok(&value));
+	assert_eq!(
+		format!("{value:?}").parse::<JsonValue>().as_ref(),
+		Ok(&value)
+	);
+	assert_eq!(JsonValue::parse_fast(&text).as_ref(), Ok(&value));
+	assert_eq!(JsonValue::from_binary(&value.to_binary()), Ok(value));
+});
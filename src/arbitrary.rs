@@ -0,0 +1,141 @@
+use crate::JsonValue;
+
+// Builds values out of raw bytes, for fuzzing and property tests. Any input gives a value, and
+// each byte decides one thing about it, so small mutations from a fuzzer make small changes to
+// the value. Once the bytes run out every choice reads as zero, which closes all open containers.
+pub struct Generator<'a> {
+	bytes: std::slice::Iter<'a, u8>,
+	max_depth: usize,
+}
+
+// Characters that are likely to trip up escaping, UTF-8 handling, or surrogate pairs
+const CHARS: [char; 16] = [
+	'"',
+	'\\',
+	'/',
+	'\u{0}',
+	'\u{1f}',
+	'\u{7f}',
+	'é',
+	'\u{7ff}',
+	'\u{800}',
+	'日',
+	'\u{2028}',
+	'\u{fffd}',
+	'\u{ffff}',
+	'\u{10000}',
+	'😀',
+	'\u{10ffff}',
+];
+
+const NUMBERS: [f64; 16] = [
+	0.,
+	-0.,
+	1.,
+	-1.,
+	0.1,
+	0.30000000000000004,
+	1e21,
+	1e-7,
+	123456789012345680000.,
+	9007199254740993.,
+	4294967296.,
+	-2147483649.,
+	f64::MAX,
+	f64::MIN,
+	f64::MIN_POSITIVE,
+	5e-324,
+];
+
+impl<'a> Generator<'a> {
+	pub fn new(bytes: &'a [u8]) -> Self {
+		Generator {
+			bytes: bytes.iter(),
+			max_depth: 16,
+		}
+	}
+
+	#[must_use]
+	pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+		self.max_depth = max_depth;
+		self
+	}
+
+	// Whether every byte has been used
+	pub fn is_empty(&self) -> bool {
+		self.bytes.len() == 0
+	}
+
+	pub fn value(&mut self) -> JsonValue {
+		struct Frame {
+			value: JsonValue,
+			remaining: u8,
+		}
+
+		let mut stack: Vec<Frame> = vec![];
+		loop {
+			let kinds = if stack.len() < self.max_depth { 8 } else { 6 };
+			let mut value = match self.byte() % kinds {
+				0 => Some(JsonValue::Null),
+				1 => Some(JsonValue::Boolean(self.byte() & 1 == 1)),
+				2 | 3 => Some(JsonValue::from(self.number())),
+				4 | 5 => Some(JsonValue::from(self.string())),
+				kind => {
+					stack.push(Frame {
+						value: match kind {
+							6 => JsonValue::list(),
+							_ => JsonValue::object(),
+						},
+						remaining: self.byte() % 8,
+					});
+					None
+				}
+			};
+
+			loop {
+				let Some(Frame {
+					value: parent,
+					remaining,
+				}) = stack.last_mut()
+				else {
+					return value.unwrap();
+				};
+				match (parent, value.take()) {
+					(JsonValue::List(ls), Some(val)) => ls.push(val),
+					(JsonValue::Object(obj), Some(val)) => {
+						obj.insert(self.string(), val);
+					}
+					_ => {}
+				}
+				if *remaining > 0 {
+					*remaining -= 1;
+					break;
+				}
+				value = stack.pop().map(|frame| frame.value);
+			}
+		}
+	}
+
+	fn byte(&mut self) -> u8 {
+		self.bytes.next().copied().unwrap_or(0)
+	}
+
+	fn number(&mut self) -> f64 {
+		match self.byte() % 4 {
+			0 => NUMBERS[(self.byte() % 16) as usize],
+			1 => self.byte() as i8 as f64,
+			// Anything that isn't finite becomes null
+			_ => f64::from_bits(u64::from_le_bytes([(); 8].map(|()| self.byte()))),
+		}
+	}
+
+	fn string(&mut self) -> String {
+		let len = self.byte() % 16;
+		(0..len)
+			.map(|_| match self.byte() {
+				byte @ 0..0x80 => byte as char,
+				byte => CHARS[(byte % 16) as usize],
+			})
+			.collect()
+	}
+}
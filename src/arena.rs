@@ -0,0 +1,450 @@
+use crate::fast::{Token, Tokens, classifier};
+use crate::{
+	DuplicateKeys, Error, FiniteF64, HashMap, JsonIndex, JsonKind, JsonValue, Key, Limit,
+	ParseError, ParseErrorKind, ParseOptions, impl_predicates,
+};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Range;
+
+// Holds a parsed document in a few flat buffers instead of a separate allocation for every string
+// and container. The buffers are kept between parses, so an arena that's reused for a stream of
+// documents stops allocating once it has grown to fit the largest of them.
+#[derive(Default)]
+pub struct JsonArena {
+	strings: String,
+	// The children of every container are stored next to each other
+	nodes: Vec<Slot>,
+	// Next to nodes, the range of each object entry's key in strings
+	keys: Vec<Range<usize>>,
+	// Entries of the containers that are still open
+	scratch: Vec<Entry>,
+}
+
+// Ranges into strings for strings, and into nodes for containers
+#[derive(Clone)]
+enum Slot {
+	Null,
+	Boolean(bool),
+	Number(FiniteF64),
+	String(Range<usize>),
+	List(Range<usize>),
+	Object(Range<usize>),
+}
+
+struct Entry {
+	key: Range<usize>,
+	key_start: usize,
+	slot: Slot,
+}
+
+#[derive(Clone, Copy)]
+pub enum ArenaValue<'arena> {
+	Null,
+	Boolean(bool),
+	Number(FiniteF64),
+	String(&'arena str),
+	List(ArenaList<'arena>),
+	Object(ArenaObject<'arena>),
+}
+
+#[derive(Clone, Copy)]
+pub struct ArenaList<'arena> {
+	arena: &'arena JsonArena,
+	start: usize,
+	end: usize,
+}
+
+// Entries are sorted by key, so lookups are a binary search
+#[derive(Clone, Copy)]
+pub struct ArenaObject<'arena> {
+	arena: &'arena JsonArena,
+	start: usize,
+	end: usize,
+}
+
+impl JsonArena {
+	pub fn new() -> Self {
+		JsonArena::default()
+	}
+
+	// Replaces whatever the arena held before
+	pub fn parse(&mut self, input: &str) -> Result<ArenaValue<'_>, Error> {
+		self.parse_with(input, &ParseOptions::default())
+	}
+
+	pub fn parse_with(
+		&mut self,
+		input: &str,
+		options: &ParseOptions,
+	) -> Result<ArenaValue<'_>, Error> {
+		self.strings.clear();
+		self.nodes.clear();
+		self.keys.clear();
+		self.scratch.clear();
+
+		let limits = &options.limits;
+		let mut tokens = Tokens::new(input, limits, classifier())?;
+		// Where each open container's entries start in scratch, and the key it's stored under
+		let mut frames = vec![];
+		let mut key = None;
+		let mut root = None;
+
+		let result = (|| -> Result<Slot, ParseErrorKind> {
+			while let Some(token) = tokens.next()? {
+				let slot = match token {
+					Token::StartList | Token::StartObject => {
+						let is_object = matches!(token, Token::StartObject);
+						frames.push((self.scratch.len(), is_object, key.take()));
+						continue;
+					}
+					Token::Key(s, start) => {
+						key = Some((self.push_str(&s), start));
+						continue;
+					}
+					Token::End => {
+						let (start, is_object, own_key) = frames.pop().unwrap();
+						key = own_key;
+						self.close(start, is_object, options.duplicate_keys)
+							.map_err(|(key, key_start)| {
+								tokens.offset = key_start;
+								ParseErrorKind::DuplicateKey(key)
+							})?
+					}
+					Token::Null => Slot::Null,
+					Token::Boolean(b) => Slot::Boolean(b),
+					Token::Number(num) => FiniteF64::try_from(num).map_or(Slot::Null, Slot::Number),
+					Token::String(s) => Slot::String(self.push_str(&s)),
+				};
+
+				match frames.last() {
+					Some(&(start, ..)) if self.scratch.len() - start >= limits.max_entries => {
+						Err(Limit::Entries)?
+					}
+					Some(_) => {
+						let (key, key_start) = key.take().unwrap_or_default();
+						self.scratch.push(Entry {
+							key,
+							key_start,
+							slot,
+						});
+					}
+					None => root = Some(slot),
+				}
+			}
+			Ok(root.take().unwrap())
+		})();
+
+		match result {
+			Ok(root) => Ok(self.value(&root)),
+			Err(kind) => Err(ParseError {
+				kind,
+				offset: tokens.offset,
+			}
+			.into()),
+		}
+	}
+
+	fn push_str(&mut self, s: &str) -> Range<usize> {
+		self.strings.push_str(s);
+		self.strings.len() - s.len()..self.strings.len()
+	}
+
+	// Moves the entries of a container that has just ended from scratch to their final place in
+	// nodes. Duplicate keys are found by sorting once the object ends, so with DuplicateKeys::Error
+	// a repeat is only reported then, naming the earliest one in that object.
+	fn close(
+		&mut self,
+		start: usize,
+		is_object: bool,
+		duplicate_keys: DuplicateKeys,
+	) -> Result<Slot, (String, usize)> {
+		let JsonArena {
+			strings,
+			nodes,
+			keys,
+			scratch,
+		} = self;
+
+		if is_object {
+			let key = |entry: &Entry| &strings[entry.key.clone()];
+			scratch[start..]
+				.sort_unstable_by(|a, b| key(a).cmp(key(b)).then(a.key_start.cmp(&b.key_start)));
+
+			// Each run of equal keys is replaced by a single entry, packed towards the start
+			let (mut read, mut write) = (start, start);
+			let mut repeat: Option<(Range<usize>, usize)> = None;
+			while read < scratch.len() {
+				let run = scratch[read..]
+					.iter()
+					.take_while(|entry| key(entry) == key(&scratch[read]))
+					.count();
+				let slot = match (run, duplicate_keys) {
+					(1, _) | (_, DuplicateKeys::FirstWins) => scratch[read].slot.clone(),
+					(_, DuplicateKeys::LastWins) => scratch[read + run - 1].slot.clone(),
+					(_, DuplicateKeys::Error) => {
+						let second = &scratch[read + 1];
+						if repeat.as_ref().is_none_or(|(_, at)| second.key_start < *at) {
+							repeat = Some((second.key.clone(), second.key_start));
+						}
+						Slot::Null
+					}
+					(_, DuplicateKeys::CollectIntoList) => {
+						let first = nodes.len();
+						nodes.extend(scratch[read..read + run].iter().map(|e| e.slot.clone()));
+						keys.extend((0..run).map(|_| 0..0));
+						Slot::List(first..nodes.len())
+					}
+				};
+				scratch[write].key = scratch[read].key.clone();
+				scratch[write].slot = slot;
+				(read, write) = (read + run, write + 1);
+			}
+			if let Some((key, key_start)) = repeat {
+				return Err((strings[key].to_string(), key_start));
+			}
+			scratch.truncate(write);
+		}
+
+		let first = nodes.len();
+		for entry in scratch.drain(start..) {
+			nodes.push(entry.slot);
+			keys.push(entry.key);
+		}
+		Ok(match is_object {
+			true => Slot::Object(first..nodes.len()),
+			false => Slot::List(first..nodes.len()),
+		})
+	}
+
+	fn value(&self, slot: &Slot) -> ArenaValue<'_> {
+		match slot {
+			Slot::Null => ArenaValue::Null,
+			Slot::Boolean(b) => ArenaValue::Boolean(*b),
+			Slot::Number(num) => ArenaValue::Number(*num),
+			Slot::String(range) => ArenaValue::String(&self.strings[range.clone()]),
+			Slot::List(range) => ArenaValue::List(ArenaList {
+				arena: self,
+				start: range.start,
+				end: range.end,
+			}),
+			Slot::Object(range) => ArenaValue::Object(ArenaObject {
+				arena: self,
+				start: range.start,
+				end: range.end,
+			}),
+		}
+	}
+
+	fn key(&self, idx: usize) -> &str {
+		&self.strings[self.keys[idx].clone()]
+	}
+}
+
+impl<'arena> ArenaValue<'arena> {
+	pub fn kind(&self) -> JsonKind {
+		match self {
+			ArenaValue::Null => JsonKind::Null,
+			ArenaValue::Boolean(_) => JsonKind::Boolean,
+			ArenaValue::Number(_) => JsonKind::Number,
+			ArenaValue::String(_) => JsonKind::String,
+			ArenaValue::List(_) => JsonKind::List,
+			ArenaValue::Object(_) => JsonKind::Object,
+		}
+	}
+
+	pub fn get(&self, index: impl JsonIndex) -> Option<ArenaValue<'arena>> {
+		match index.key() {
+			Key::Index(idx) => self.as_list()?.get(idx),
+			Key::Name(key) => self.as_object()?.get(key),
+		}
+	}
+
+	// Like get, but returns null instead of None
+	pub fn at(&self, index: impl JsonIndex) -> ArenaValue<'arena> {
+		self.get(index).unwrap_or(ArenaValue::Null)
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		match self {
+			ArenaValue::Boolean(b) => Some(*b),
+			_ => None,
+		}
+	}
+
+	pub fn as_f64(&self) -> Option<f64> {
+		match self {
+			ArenaValue::Number(num) => Some(num.into()),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> Option<&'arena str> {
+		match self {
+			ArenaValue::String(s) => Some(s),
+			_ => None,
+		}
+	}
+
+	pub fn as_list(&self) -> Option<ArenaList<'arena>> {
+		match self {
+			ArenaValue::List(ls) => Some(*ls),
+			_ => None,
+		}
+	}
+
+	pub fn as_object(&self) -> Option<ArenaObject<'arena>> {
+		match self {
+			ArenaValue::Object(obj) => Some(*obj),
+			_ => None,
+		}
+	}
+
+	pub fn into_owned(self) -> JsonValue {
+		struct Frame<'arena> {
+			value: JsonValue,
+			arena: &'arena JsonArena,
+			children: Range<usize>,
+			key: &'arena str,
+		}
+
+		let mut stack: Vec<Frame> = vec![];
+		let mut next = self;
+
+		loop {
+			let mut value = match next {
+				ArenaValue::Null => Some(JsonValue::Null),
+				ArenaValue::Boolean(b) => Some(JsonValue::Boolean(b)),
+				ArenaValue::Number(num) => Some(JsonValue::Number(num)),
+				ArenaValue::String(s) => Some(JsonValue::from(s)),
+				ArenaValue::List(ArenaList { arena, start, end }) => {
+					stack.push(Frame {
+						value: JsonValue::List(Vec::with_capacity(end - start)),
+						arena,
+						children: start..end,
+						key: "",
+					});
+					None
+				}
+				ArenaValue::Object(ArenaObject { arena, start, end }) => {
+					stack.push(Frame {
+						value: JsonValue::from(HashMap::with_capacity(end - start)),
+						arena,
+						children: start..end,
+						key: "",
+					});
+					None
+				}
+			};
+
+			loop {
+				let Some(Frame {
+					value: parent,
+					arena,
+					children,
+					key,
+				}) = stack.last_mut()
+				else {
+					return value.unwrap();
+				};
+				match (parent, value.take()) {
+					(JsonValue::List(ls), Some(val)) => ls.push(val),
+					(JsonValue::Object(obj), Some(val)) => {
+						obj.insert(key.to_string(), val);
+					}
+					_ => {}
+				}
+				if let Some(idx) = children.next() {
+					*key = arena.key(idx);
+					next = arena.value(&arena.nodes[idx]);
+					break;
+				}
+				value = stack.pop().map(|frame| frame.value);
+			}
+		}
+	}
+}
+
+impl_predicates!(ArenaValue<'arena>);
+
+impl<'arena> ArenaList<'arena> {
+	pub fn len(&self) -> usize {
+		self.end - self.start
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, idx: usize) -> Option<ArenaValue<'arena>> {
+		(idx < self.len()).then(|| self.arena.value(&self.arena.nodes[self.start + idx]))
+	}
+
+	pub fn iter(&self) -> impl ExactSizeIterator<Item = ArenaValue<'arena>> + use<'arena> {
+		let arena = self.arena;
+		arena.nodes[self.start..self.end]
+			.iter()
+			.map(move |slot| arena.value(slot))
+	}
+}
+
+impl<'arena> ArenaObject<'arena> {
+	pub fn len(&self) -> usize {
+		self.end - self.start
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, key: &str) -> Option<ArenaValue<'arena>> {
+		let (mut low, mut high) = (self.start, self.end);
+		while low < high {
+			let mid = low + (high - low) / 2;
+			match self.arena.key(mid).cmp(key) {
+				std::cmp::Ordering::Less => low = mid + 1,
+				std::cmp::Ordering::Greater => high = mid,
+				std::cmp::Ordering::Equal => return Some(self.arena.value(&self.arena.nodes[mid])),
+			}
+		}
+		None
+	}
+
+	// In key order
+	pub fn iter(
+		&self,
+	) -> impl ExactSizeIterator<Item = (&'arena str, ArenaValue<'arena>)> + use<'arena> {
+		let arena = self.arena;
+		(self.start..self.end).map(move |idx| (arena.key(idx), arena.value(&arena.nodes[idx])))
+	}
+}
+
+impl From<ArenaValue<'_>> for JsonValue {
+	fn from(value: ArenaValue<'_>) -> Self {
+		value.into_owned()
+	}
+}
+
+// Formatting goes through the owned value
+impl Display for ArenaValue<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{}", self.into_owned())
+	}
+}
+
+impl Debug for ArenaValue<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{:?}", self.into_owned())
+	}
+}
+
+impl Debug for ArenaList<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{:?}", ArenaValue::List(*self))
+	}
+}
+
+impl Debug for ArenaObject<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{:?}", ArenaValue::Object(*self))
+	}
+}
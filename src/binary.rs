@@ -0,0 +1,269 @@
+use crate::{Error, FiniteF64, HashMap, JsonValue, ParseError, ParseErrorKind};
+
+const MAGIC: &[u8; 4] = b"MJSN";
+const VERSION: u8 = 1;
+const FLAG_KEY_TABLE: u8 = 1;
+
+pub(crate) const NULL: u8 = 0;
+pub(crate) const FALSE: u8 = 1;
+pub(crate) const TRUE: u8 = 2;
+pub(crate) const NUMBER: u8 = 3;
+pub(crate) const STRING: u8 = 4;
+pub(crate) const LIST: u8 = 5;
+pub(crate) const OBJECT: u8 = 6;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryOptions {
+	// Store each distinct object key once, and refer to it by index everywhere it's used
+	pub dedup_keys: bool,
+}
+
+impl Default for BinaryOptions {
+	fn default() -> Self {
+		BinaryOptions { dedup_keys: true }
+	}
+}
+
+impl JsonValue {
+	// A snapshot format that loads much faster than parsing text. The header carries a format
+	// version, and from_binary rejects versions it doesn't know.
+	pub fn to_binary(&self) -> Vec<u8> {
+		self.to_binary_with(&BinaryOptions::default())
+	}
+
+	pub fn to_binary_with(&self, options: &BinaryOptions) -> Vec<u8> {
+		let mut out = MAGIC.to_vec();
+		out.push(VERSION);
+		out.push(if options.dedup_keys {
+			FLAG_KEY_TABLE
+		} else {
+			0
+		});
+
+		let mut keys = HashMap::new();
+		if options.dedup_keys {
+			let mut table = vec![];
+			let mut stack = vec![self];
+			while let Some(value) = stack.pop() {
+				match value {
+					JsonValue::List(ls) => stack.extend(ls),
+					JsonValue::Object(obj) => {
+						for (key, val) in obj {
+							keys.entry(key.as_str()).or_insert_with(|| {
+								table.push(key.as_str());
+								table.len() - 1
+							});
+							stack.push(val);
+						}
+					}
+					_ => {}
+				}
+			}
+			write_len(&mut out, table.len());
+			for key in table {
+				write_str(&mut out, key);
+			}
+		}
+
+		let mut stack = vec![self];
+		while let Some(value) = stack.pop() {
+			match value {
+				JsonValue::Null => out.push(NULL),
+				JsonValue::Boolean(false) => out.push(FALSE),
+				JsonValue::Boolean(true) => out.push(TRUE),
+				JsonValue::Number(num) => {
+					out.push(NUMBER);
+					out.extend(f64::from(num).to_le_bytes());
+				}
+				JsonValue::String(s) => {
+					out.push(STRING);
+					write_str(&mut out, s);
+				}
+				JsonValue::List(ls) => {
+					out.push(LIST);
+					write_len(&mut out, ls.len());
+					stack.extend(ls.iter().rev());
+				}
+				// Keys are written up front so the loader can size the map and read entries in
+				// the order their values come off the stack
+				JsonValue::Object(obj) => {
+					out.push(OBJECT);
+					write_len(&mut out, obj.len());
+					for key in obj.keys() {
+						match keys.get(key.as_str()) {
+							Some(&idx) => write_len(&mut out, idx),
+							None => write_str(&mut out, key),
+						}
+					}
+					let values: Vec<_> = obj.values().collect();
+					stack.extend(values.into_iter().rev());
+				}
+			}
+		}
+		out
+	}
+
+	pub fn from_binary(bytes: &[u8]) -> Result<JsonValue, Error> {
+		struct Container {
+			value: JsonValue,
+			remaining: usize,
+			// Keys still waiting for their values, in reverse
+			keys: Vec<String>,
+		}
+
+		let mut i = 0;
+		let result = (|| -> Result<JsonValue, ParseErrorKind> {
+			if bytes.get(..4) != Some(MAGIC) {
+				Err("not a microjson binary snapshot")?
+			}
+			i = 4;
+			let [version, flags] = take(bytes, &mut i)?;
+			if version != VERSION {
+				i = 4;
+				Err(format!("unsupported binary format version {version}"))?
+			}
+
+			if flags & !FLAG_KEY_TABLE != 0 {
+				i = 5;
+				Err("unknown binary format flags")?
+			}
+			let dedup_keys = flags & FLAG_KEY_TABLE != 0;
+			let mut table = vec![];
+			if dedup_keys {
+				let len = read_len(bytes, &mut i)?;
+				table.reserve(len.min(bytes.len() - i));
+				for _ in 0..len {
+					table.push(read_str(bytes, &mut i)?);
+				}
+			}
+
+			let mut stack: Vec<Container> = vec![];
+			loop {
+				let next = match stack.last() {
+					Some(Container { remaining: 0, .. }) => stack.pop().unwrap().value,
+					_ => {
+						let [tag] = take(bytes, &mut i)?;
+						match tag {
+							NULL => JsonValue::Null,
+							FALSE => JsonValue::Boolean(false),
+							TRUE => JsonValue::Boolean(true),
+							NUMBER => {
+								let num = f64::from_le_bytes(take(bytes, &mut i)?);
+								JsonValue::Number(FiniteF64::try_from(num).map_err(|_| {
+									i -= 8;
+									"invalid number"
+								})?)
+							}
+							STRING => JsonValue::String(read_str(bytes, &mut i)?.into()),
+							LIST => {
+								let len = read_len(bytes, &mut i)?;
+								stack.push(Container {
+									value: JsonValue::List(Vec::with_capacity(
+										len.min(bytes.len() - i),
+									)),
+									remaining: len,
+									keys: vec![],
+								});
+								continue;
+							}
+							OBJECT => {
+								let len = read_len(bytes, &mut i)?;
+								let mut keys = Vec::with_capacity(len.min(bytes.len() - i));
+								for _ in 0..len {
+									keys.push(match dedup_keys {
+										true => {
+											let start = i;
+											let idx = read_len(bytes, &mut i)?;
+											let key = table.get(idx).ok_or_else(|| {
+												i = start;
+												"invalid key index"
+											})?;
+											key.to_string()
+										}
+										false => read_str(bytes, &mut i)?.to_owned(),
+									});
+								}
+								keys.reverse();
+								stack.push(Container {
+									value: JsonValue::from(HashMap::with_capacity(keys.len())),
+									remaining: len,
+									keys,
+								});
+								continue;
+							}
+							_ => {
+								i -= 1;
+								Err("invalid tag")?
+							}
+						}
+					}
+				};
+
+				let Some(parent) = stack.last_mut() else {
+					return match i == bytes.len() {
+						true => Ok(next),
+						false => Err("unexpected trailing bytes".into()),
+					};
+				};
+				parent.remaining -= 1;
+				match &mut parent.value {
+					JsonValue::List(ls) => ls.push(next),
+					JsonValue::Object(obj) => {
+						obj.insert(parent.keys.pop().unwrap(), next);
+					}
+					_ => unreachable!(),
+				}
+			}
+		})();
+
+		result.map_err(|kind| ParseError { kind, offset: i }.into())
+	}
+}
+
+fn write_len(out: &mut Vec<u8>, mut len: usize) {
+	while len >= 0x80 {
+		out.push(len as u8 | 0x80);
+		len >>= 7;
+	}
+	out.push(len as u8);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+	write_len(out, s.len());
+	out.extend_from_slice(s.as_bytes());
+}
+
+fn take<const N: usize>(bytes: &[u8], i: &mut usize) -> Result<[u8; N], ParseErrorKind> {
+	let arr = bytes
+		.get(*i..*i + N)
+		.ok_or("unexpected end of input")?
+		.try_into()
+		.unwrap();
+	*i += N;
+	Ok(arr)
+}
+
+// LEB128
+fn read_len(bytes: &[u8], i: &mut usize) -> Result<usize, ParseErrorKind> {
+	let mut len = 0_usize;
+	for shift in (0..usize::BITS).step_by(7) {
+		let [byte] = take(bytes, i)?;
+		len |= ((byte & 0x7f) as usize)
+			.checked_shl(shift)
+			.filter(|part| part >> shift == (byte & 0x7f) as usize)
+			.ok_or("invalid length")?;
+		if byte & 0x80 == 0 {
+			return Ok(len);
+		}
+	}
+	Err("invalid length".into())
+}
+
+fn read_str<'b>(bytes: &'b [u8], i: &mut usize) -> Result<&'b str, ParseErrorKind> {
+	let len = read_len(bytes, i)?;
+	let end = i.checked_add(len).ok_or("invalid length")?;
+	let s = bytes.get(*i..end).ok_or("unexpected end of input")?;
+	let s = std::str::from_utf8(s).map_err(|_| "invalid UTF-8")?;
+	*i = end;
+	Ok(s)
+}
@@ -0,0 +1,261 @@
+use crate::{
+	Builder, Error, FiniteF64, HashMap, JsonKind, JsonValue, Key, Node, ParseOptions, Tree,
+	TypeError, build_from, drop_tree, index, index_mut, parse_all, serialize, str_key, tree_eq,
+};
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem::take;
+use std::ops::{Index, IndexMut};
+
+pub enum JsonValueRef<'a> {
+	Null,
+	Boolean(bool),
+	Number(FiniteF64),
+	String(Cow<'a, str>),
+	List(Vec<JsonValueRef<'a>>),
+	Object(HashMap<Cow<'a, str>, JsonValueRef<'a>>),
+}
+
+pub fn parse_borrowed(input: &str) -> Result<JsonValueRef<'_>, Error> {
+	JsonValueRef::parse_with(input, &ParseOptions::default())
+}
+
+impl<'a> JsonValueRef<'a> {
+	pub fn parse_with(input: &'a str, options: &ParseOptions) -> Result<Self, Error> {
+		parse_all(input, options).map_err(Error::from)
+	}
+
+	pub fn kind(&self) -> JsonKind {
+		Tree::kind(self)
+	}
+
+	pub fn into_owned(self) -> JsonValue {
+		build_from(&self)
+	}
+}
+
+impl Drop for JsonValueRef<'_> {
+	fn drop(&mut self) {
+		drop_tree(self)
+	}
+}
+
+impl PartialEq for JsonValueRef<'_> {
+	fn eq(&self, rhs: &Self) -> bool {
+		tree_eq(self, rhs)
+	}
+}
+
+impl Eq for JsonValueRef<'_> {}
+
+impl From<JsonValueRef<'_>> for JsonValue {
+	fn from(value: JsonValueRef<'_>) -> Self {
+		value.into_owned()
+	}
+}
+
+macro_rules! impl_try_from {
+	($($kind:ident: $type:ty),*) => { $(
+		impl<'a> TryFrom<JsonValueRef<'a>> for $type {
+			type Error = Error;
+
+			fn try_from(mut value: JsonValueRef<'a>) -> Result<Self, Self::Error> {
+				match &mut value {
+					JsonValueRef::$kind(val) => Ok(take(val)),
+					_ => Err(TypeError::new(JsonKind::$kind, value.kind()).into()),
+				}
+			}
+		}
+	)* }
+}
+
+impl_try_from!(
+	Boolean: bool,
+	String: Cow<'a, str>,
+	List: Vec<JsonValueRef<'a>>,
+	Object: HashMap<Cow<'a, str>, JsonValueRef<'a>>
+);
+
+macro_rules! impl_try_from_ref {
+	($($in:ty: $kind:ident => $out:ty),*) => { $(
+		impl<'a, 'b> TryFrom<$in> for $out {
+			type Error = Error;
+
+			fn try_from(value: $in) -> Result<Self, Self::Error> {
+				match value {
+					JsonValueRef::$kind(val) => Ok(val.into()),
+					_ => Err(TypeError::new(JsonKind::$kind, value.kind()).into()),
+				}
+			}
+		}
+	)* }
+}
+
+impl_try_from_ref!(
+	JsonValueRef<'a>: Number => f64,
+	&'b JsonValueRef<'a>: Number => f64,
+	&'b mut JsonValueRef<'a>: Number => f64,
+
+	&'b JsonValueRef<'a>: Boolean => &'b bool,
+	&'b mut JsonValueRef<'a>: Boolean => &'b mut bool,
+
+	&'b JsonValueRef<'a>: String => &'b Cow<'a, str>,
+	&'b mut JsonValueRef<'a>: String => &'b mut Cow<'a, str>,
+
+	&'b JsonValueRef<'a>: List => &'b Vec<JsonValueRef<'a>>,
+	&'b mut JsonValueRef<'a>: List => &'b mut Vec<JsonValueRef<'a>>,
+
+	&'b JsonValueRef<'a>: Object => &'b HashMap<Cow<'a, str>, JsonValueRef<'a>>,
+	&'b mut JsonValueRef<'a>: Object => &'b mut HashMap<Cow<'a, str>, JsonValueRef<'a>>
+);
+
+impl<'a> Index<usize> for JsonValueRef<'a> {
+	type Output = JsonValueRef<'a>;
+
+	fn index(&self, idx: usize) -> &Self::Output {
+		index(self, Key::Index(idx))
+	}
+}
+
+impl<'a> Index<&str> for JsonValueRef<'a> {
+	type Output = JsonValueRef<'a>;
+
+	fn index(&self, key: &str) -> &Self::Output {
+		index(self, Key::Name(key))
+	}
+}
+
+impl IndexMut<usize> for JsonValueRef<'_> {
+	fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+		index_mut(self, Key::Index(idx))
+	}
+}
+
+impl IndexMut<&str> for JsonValueRef<'_> {
+	fn index_mut(&mut self, key: &str) -> &mut Self::Output {
+		index_mut(self, Key::Name(key))
+	}
+}
+
+impl<'a> Tree for JsonValueRef<'a> {
+	type ListIter<'v>
+		= std::slice::Iter<'v, JsonValueRef<'a>>
+	where
+		Self: 'v;
+	type ObjectIter<'v>
+		= std::iter::Map<
+		std::collections::hash_map::Iter<'v, Cow<'a, str>, JsonValueRef<'a>>,
+		fn((&'v Cow<'a, str>, &'v JsonValueRef<'a>)) -> (&'v str, &'v JsonValueRef<'a>),
+	>
+	where
+		Self: 'v;
+
+	fn node(&self) -> Node<'_, Self> {
+		match self {
+			JsonValueRef::Null => Node::Null,
+			JsonValueRef::Boolean(b) => Node::Boolean(*b),
+			JsonValueRef::Number(num) => Node::Number(num.into()),
+			JsonValueRef::String(s) => Node::String(s),
+			JsonValueRef::List(ls) => Node::List(ls.iter()),
+			JsonValueRef::Object(obj) => Node::Object(obj.iter().map(str_key as _)),
+		}
+	}
+
+	fn items(&self) -> Option<&[Self]> {
+		match self {
+			JsonValueRef::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn items_mut(&mut self) -> Option<&mut [Self]> {
+		match self {
+			JsonValueRef::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn lookup(&self, key: &str) -> Option<&Self> {
+		match self {
+			JsonValueRef::Object(obj) => obj.get(key),
+			_ => None,
+		}
+	}
+
+	fn lookup_mut(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			JsonValueRef::Object(obj) => obj.get_mut(key),
+			_ => None,
+		}
+	}
+
+	fn entry(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			JsonValueRef::Object(obj) => Some(
+				obj.entry(Cow::Owned(key.into()))
+					.or_insert(JsonValueRef::Null),
+			),
+			_ => None,
+		}
+	}
+
+	fn take_children(&mut self, out: &mut Vec<Self>) {
+		match self {
+			JsonValueRef::List(ls) => out.append(ls),
+			JsonValueRef::Object(obj) => out.extend(take(obj).into_values()),
+			_ => {}
+		}
+	}
+}
+
+impl<'a> Builder<'a> for JsonValueRef<'a> {
+	type Keys = ();
+
+	fn null() -> Self {
+		JsonValueRef::Null
+	}
+
+	fn boolean(val: bool) -> Self {
+		JsonValueRef::Boolean(val)
+	}
+
+	fn number(val: f64) -> Self {
+		FiniteF64::try_from(val).map_or(JsonValueRef::Null, JsonValueRef::Number)
+	}
+
+	fn string(val: Cow<'a, str>) -> Self {
+		JsonValueRef::String(val)
+	}
+
+	fn list() -> Self {
+		JsonValueRef::List(Vec::new())
+	}
+
+	fn object() -> Self {
+		JsonValueRef::Object(HashMap::new())
+	}
+
+	fn push_value(&mut self, val: Self) {
+		if let JsonValueRef::List(ls) = self {
+			ls.push(val);
+		}
+	}
+
+	fn insert_value(&mut self, _: &mut (), key: Cow<'a, str>, val: Self) {
+		if let JsonValueRef::Object(obj) = self {
+			obj.insert(key, val);
+		}
+	}
+}
+
+impl Display for JsonValueRef<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, false)
+	}
+}
+
+impl Debug for JsonValueRef<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, true)
+	}
+}
@@ -0,0 +1,480 @@
+use crate::{
+	Error, JsonKind, JsonValue, Limit, ParseError, ParseErrorKind, ParseLimits, TypeError,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+	pub limits: ParseLimits,
+	// Represent BSON-only types such as ObjectId and dates using relaxed Extended JSON v2,
+	// instead of rejecting them
+	pub extended_json: bool,
+}
+
+// Lists are stored as documents keyed by index, so only objects can be encoded at the top level
+pub fn to_vec(value: &JsonValue) -> Result<Vec<u8>, Error> {
+	enum Key<'v> {
+		Str(&'v str),
+		Index(usize),
+	}
+	enum Item<'v> {
+		Element(Key<'v>, &'v JsonValue),
+		Close(usize),
+	}
+
+	let JsonValue::Object(obj) = value else {
+		return Err(TypeError::new(JsonKind::Object, value.kind())
+			.with_path("")
+			.into());
+	};
+
+	let mut out = vec![0; 4];
+	let mut stack = vec![Item::Close(0)];
+	stack.extend(
+		obj.iter()
+			.map(|(key, val)| Item::Element(Key::Str(key), val)),
+	);
+	while let Some(item) = stack.pop() {
+		let (key, value) = match item {
+			Item::Element(key, value) => (key, value),
+			Item::Close(start) => {
+				out.push(0);
+				let size = i32::try_from(out.len() - start)
+					.map_err(|_| Error::Encode("document is too large".into()))?;
+				out[start..start + 4].copy_from_slice(&size.to_le_bytes());
+				continue;
+			}
+		};
+
+		let element_type = match value {
+			JsonValue::Null => 0x0a,
+			JsonValue::Boolean(_) => 0x08,
+			JsonValue::Number(num) => {
+				const LIMIT: f64 = 9223372036854775808.;
+				let num = f64::from(num);
+				let is_negative_zero = num == 0. && num.is_sign_negative();
+				if num.fract() != 0. || is_negative_zero || !(-LIMIT..LIMIT).contains(&num) {
+					0x01
+				} else if i32::try_from(num as i64).is_ok() {
+					0x10
+				} else {
+					0x12
+				}
+			}
+			JsonValue::String(_) => 0x02,
+			JsonValue::Object(_) => 0x03,
+			JsonValue::List(_) => 0x04,
+		};
+		out.push(element_type);
+		match key {
+			Key::Str(key) if key.contains('\0') => {
+				return Err(Error::Encode(format!("key {key:?} contains a NUL byte")));
+			}
+			Key::Str(key) => out.extend_from_slice(key.as_bytes()),
+			Key::Index(idx) => out.extend_from_slice(idx.to_string().as_bytes()),
+		}
+		out.push(0);
+
+		match (value, element_type) {
+			(JsonValue::Boolean(b), _) => out.push(*b as u8),
+			(JsonValue::Number(num), 0x10) => out.extend((f64::from(num) as i32).to_le_bytes()),
+			(JsonValue::Number(num), 0x12) => out.extend((f64::from(num) as i64).to_le_bytes()),
+			(JsonValue::Number(num), _) => out.extend(f64::from(num).to_le_bytes()),
+			(JsonValue::String(s), _) => {
+				let len = i32::try_from(s.len() + 1)
+					.map_err(|_| Error::Encode("string is too large".into()))?;
+				out.extend(len.to_le_bytes());
+				out.extend_from_slice(s.as_bytes());
+				out.push(0);
+			}
+			(JsonValue::Object(obj), _) => {
+				stack.push(Item::Close(out.len()));
+				out.extend([0; 4]);
+				stack.extend(
+					obj.iter()
+						.map(|(key, val)| Item::Element(Key::Str(key), val)),
+				);
+			}
+			(JsonValue::List(ls), _) => {
+				stack.push(Item::Close(out.len()));
+				out.extend([0; 4]);
+				stack.extend(
+					ls.iter()
+						.enumerate()
+						.rev()
+						.map(|(idx, val)| Item::Element(Key::Index(idx), val)),
+				);
+			}
+			(JsonValue::Null, _) => {}
+		}
+	}
+	Ok(out)
+}
+
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue, Error> {
+	from_slice_with(bytes, &DecodeOptions::default())
+}
+
+pub fn from_slice_with(bytes: &[u8], options: &DecodeOptions) -> Result<JsonValue, Error> {
+	struct Document {
+		value: JsonValue,
+		end: usize,
+		key: Option<String>,
+		// Set for the scope of a code with scope element
+		code: Option<String>,
+	}
+
+	let limits = &options.limits;
+	if bytes.len() > limits.max_bytes {
+		return Err(ParseError {
+			kind: Limit::Bytes.into(),
+			offset: limits.max_bytes,
+		}
+		.into());
+	}
+
+	let mut i = 0;
+	let result = (|| -> Result<JsonValue, ParseErrorKind> {
+		let open = |i: &mut usize, stack: &mut Vec<Document>, value, code| {
+			if stack.len() >= limits.max_depth {
+				Err(Limit::Depth)?
+			}
+			let start = *i;
+			let size = read_i32(bytes, i)?;
+			let end = start.saturating_add(size.try_into().map_err(|_| "invalid document size")?);
+			if size < 5 || end > stack.last().map_or(bytes.len(), |doc| doc.end) {
+				Err("invalid document size")?
+			}
+			stack.push(Document {
+				value,
+				end,
+				key: None,
+				code,
+			});
+			Ok::<_, ParseErrorKind>(())
+		};
+		let mut stack = vec![];
+		open(&mut i, &mut stack, JsonValue::object(), None)?;
+		loop {
+			let doc = stack.last_mut().unwrap();
+			if i >= doc.end {
+				Err("document is missing its terminator")?
+			}
+			let element_type = bytes[i];
+			if !options.extended_json
+				&& matches!(
+					element_type,
+					0x05 | 0x07 | 0x09 | 0x0b..=0x0f | 0x11 | 0x13 | 0x7f | 0xff
+				) {
+				Err(format!(
+					"BSON type 0x{element_type:02x} has no JSON equivalent"
+				))?
+			}
+			let start = i;
+			i += 1;
+
+			let next = if element_type == 0 {
+				if i != doc.end {
+					Err("document size mismatch")?
+				}
+				let doc = stack.pop().unwrap();
+				match doc.code {
+					Some(code) => JsonValue::object()
+						.with("$code", code)
+						.with("$scope", doc.value),
+					None => doc.value,
+				}
+			} else {
+				doc.key = Some(read_cstring(bytes, &mut i)?.into());
+				match element_type {
+					0x01 => match f64::from_le_bytes(take(bytes, &mut i)?) {
+						num if num.is_finite() || !options.extended_json => JsonValue::from(num),
+						num => JsonValue::object().with(
+							"$numberDouble",
+							match num {
+								f64::INFINITY => "Infinity",
+								f64::NEG_INFINITY => "-Infinity",
+								_ => "NaN",
+							},
+						),
+					},
+					0x02 => JsonValue::String(read_string(bytes, &mut i, limits)?.into()),
+					0x03 => {
+						open(&mut i, &mut stack, JsonValue::object(), None)?;
+						continue;
+					}
+					0x04 => {
+						open(&mut i, &mut stack, JsonValue::list(), None)?;
+						continue;
+					}
+					0x05 => {
+						let len = usize::try_from(read_i32(bytes, &mut i)?)
+							.map_err(|_| "invalid binary length")?;
+						let [subtype] = take(bytes, &mut i)?;
+						let data = bytes.get(i..i + len).ok_or("unexpected end of input")?;
+						i += len;
+						// The old binary subtype repeats the length inside the data
+						let data = match subtype {
+							0x02 => data.get(4..).unwrap_or_default(),
+							_ => data,
+						};
+						wrap(
+							"$binary",
+							JsonValue::object()
+								.with("base64", base64(data))
+								.with("subType", format!("{subtype:02x}")),
+						)
+					}
+					0x06 if options.extended_json => wrap("$undefined", true),
+					0x06 | 0x0a => JsonValue::Null,
+					0x07 => wrap("$oid", hex(&take::<12>(bytes, &mut i)?)),
+					0x08 => match take(bytes, &mut i)? {
+						[0] => JsonValue::Boolean(false),
+						[1] => JsonValue::Boolean(true),
+						_ => Err("invalid boolean")?,
+					},
+					0x09 => {
+						let millis = i64::from_le_bytes(take(bytes, &mut i)?);
+						let date = match millis {
+							0..=253402300799999 => JsonValue::from(format_date(millis)),
+							_ => wrap("$numberLong", millis.to_string()),
+						};
+						wrap("$date", date)
+					}
+					0x0b => {
+						let pattern = read_cstring(bytes, &mut i)?;
+						let flags = read_cstring(bytes, &mut i)?;
+						wrap(
+							"$regularExpression",
+							JsonValue::object()
+								.with("pattern", pattern)
+								.with("options", flags),
+						)
+					}
+					0x0c => {
+						let namespace = read_string(bytes, &mut i, limits)?;
+						let id = hex(&take::<12>(bytes, &mut i)?);
+						wrap(
+							"$dbPointer",
+							JsonValue::object()
+								.with("$ref", namespace)
+								.with("$id", wrap("$oid", id)),
+						)
+					}
+					0x0d | 0x0e => {
+						let s = read_string(bytes, &mut i, limits)?;
+						let key = if element_type == 0x0d {
+							"$code"
+						} else {
+							"$symbol"
+						};
+						wrap(key, s)
+					}
+					0x0f => {
+						let code_start = i;
+						let size = read_i32(bytes, &mut i)?;
+						let code = read_string(bytes, &mut i, limits)?.to_owned();
+						open(&mut i, &mut stack, JsonValue::object(), Some(code))?;
+						let end = usize::try_from(size)
+							.ok()
+							.and_then(|size| code_start.checked_add(size));
+						if end != Some(stack.last().unwrap().end) {
+							Err("code with scope size mismatch")?
+						}
+						continue;
+					}
+					0x10 => JsonValue::from(i32::from_le_bytes(take(bytes, &mut i)?)),
+					0x11 => {
+						let timestamp = u64::from_le_bytes(take(bytes, &mut i)?);
+						wrap(
+							"$timestamp",
+							JsonValue::object()
+								.with("t", (timestamp >> 32) as u32)
+								.with("i", timestamp as u32),
+						)
+					}
+					0x12 => match i64::from_le_bytes(take(bytes, &mut i)?) {
+						num if options.extended_json && num.unsigned_abs() > 1 << 53 => {
+							wrap("$numberLong", num.to_string())
+						}
+						num => JsonValue::from(num as f64),
+					},
+					0x13 => {
+						let decimal = decimal128(take(bytes, &mut i)?);
+						wrap("$numberDecimal", decimal)
+					}
+					0xff => wrap("$minKey", 1),
+					0x7f => wrap("$maxKey", 1),
+					_ => {
+						i = start;
+						Err(format!("unknown BSON type 0x{element_type:02x}"))?
+					}
+				}
+			};
+
+			let Some(parent) = stack.last_mut() else {
+				return match i == bytes.len() {
+					true => Ok(next),
+					false => Err("unexpected trailing bytes".into()),
+				};
+			};
+			let key = parent.key.take().unwrap();
+			match &mut parent.value {
+				JsonValue::List(ls) if ls.len() >= limits.max_entries => Err(Limit::Entries)?,
+				JsonValue::Object(obj) if obj.len() >= limits.max_entries => Err(Limit::Entries)?,
+				JsonValue::List(ls) => ls.push(next),
+				JsonValue::Object(obj) => {
+					obj.insert(key, next);
+				}
+				_ => unreachable!(),
+			}
+		}
+	})();
+
+	result.map_err(|kind| ParseError { kind, offset: i }.into())
+}
+
+fn wrap(key: &str, value: impl Into<JsonValue>) -> JsonValue {
+	JsonValue::object().with(key, value)
+}
+
+fn take<const N: usize>(bytes: &[u8], i: &mut usize) -> Result<[u8; N], ParseErrorKind> {
+	let arr = bytes
+		.get(*i..*i + N)
+		.ok_or("unexpected end of input")?
+		.try_into()
+		.unwrap();
+	*i += N;
+	Ok(arr)
+}
+
+fn read_i32(bytes: &[u8], i: &mut usize) -> Result<i32, ParseErrorKind> {
+	take(bytes, i).map(i32::from_le_bytes)
+}
+
+fn read_cstring<'b>(bytes: &'b [u8], i: &mut usize) -> Result<&'b str, ParseErrorKind> {
+	let rest = bytes.get(*i..).unwrap_or_default();
+	let len = rest
+		.iter()
+		.position(|&b| b == 0)
+		.ok_or("missing NUL terminator")?;
+	let s = std::str::from_utf8(&rest[..len]).map_err(|_| "invalid UTF-8")?;
+	*i += len + 1;
+	Ok(s)
+}
+
+fn read_string<'b>(
+	bytes: &'b [u8],
+	i: &mut usize,
+	limits: &ParseLimits,
+) -> Result<&'b str, ParseErrorKind> {
+	let len = usize::try_from(read_i32(bytes, i)?)
+		.ok()
+		.filter(|&len| len > 0)
+		.ok_or("invalid string length")?;
+	if len - 1 > limits.max_string_len {
+		Err(Limit::StringLength)?
+	}
+	let s = bytes.get(*i..*i + len).ok_or("unexpected end of input")?;
+	if s[len - 1] != 0 {
+		Err("missing NUL terminator")?
+	}
+	let s = std::str::from_utf8(&s[..len - 1]).map_err(|_| "invalid UTF-8")?;
+	*i += len;
+	Ok(s)
+}
+
+fn hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn base64(data: &[u8]) -> String {
+	const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+	let mut out = String::new();
+	for chunk in data.chunks(3) {
+		let bits = chunk
+			.iter()
+			.enumerate()
+			.fold(0, |acc, (idx, &b)| acc | (b as u32) << (16 - 8 * idx));
+		for idx in 0..4 {
+			out.push(match idx <= chunk.len() {
+				true => ALPHABET[(bits >> (18 - 6 * idx)) as usize & 63] as char,
+				false => '=',
+			});
+		}
+	}
+	out
+}
+
+// ISO 8601 in UTC, using the days-to-civil algorithm from Howard Hinnant's date library
+fn format_date(millis: i64) -> String {
+	let (days, millis) = (millis.div_euclid(86400000), millis.rem_euclid(86400000));
+	let days = days + 719468;
+	let era = days.div_euclid(146097);
+	let day_of_era = days.rem_euclid(146097);
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+	let mut date = format!(
+		"{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+		millis / 3600000,
+		millis / 60000 % 60,
+		millis / 1000 % 60
+	);
+	if millis % 1000 != 0 {
+		date += &format!(".{:03}", millis % 1000);
+	}
+	date + "Z"
+}
+
+// The string form of an IEEE 754 decimal128 in binary integer decimal encoding
+fn decimal128(bytes: [u8; 16]) -> String {
+	let bits = u128::from_le_bytes(bytes);
+	let sign = if bits >> 127 == 1 { "-" } else { "" };
+	match (bits >> 122) & 0x1f {
+		0x1f => return "NaN".into(),
+		0x1e => return format!("{sign}Infinity"),
+		_ => {}
+	}
+
+	// Coefficients in the second form are always too large, and too large means zero
+	let (exponent, coefficient) = match (bits >> 125) & 0b11 {
+		0b11 => ((bits >> 111) & 0x3fff, 0),
+		_ => ((bits >> 113) & 0x3fff, bits & ((1 << 113) - 1)),
+	};
+	let coefficient = if coefficient < 10_u128.pow(34) {
+		coefficient
+	} else {
+		0
+	};
+	let exponent = exponent as i32 - 6176;
+	let digits = coefficient.to_string();
+	let adjusted = exponent + digits.len() as i32 - 1;
+
+	if exponent <= 0 && adjusted >= -6 {
+		let point = digits.len() as i32 + exponent;
+		match point {
+			_ if exponent == 0 => format!("{sign}{digits}"),
+			1.. => format!(
+				"{sign}{}.{}",
+				&digits[..point as usize],
+				&digits[point as usize..]
+			),
+			_ => format!("{sign}0.{}{digits}", "0".repeat(-point as usize)),
+		}
+	} else {
+		let fraction = match digits.len() {
+			1 => String::new(),
+			_ => format!(".{}", &digits[1..]),
+		};
+		format!("{sign}{}{fraction}E{adjusted:+}", &digits[..1])
+	}
+}
@@ -0,0 +1,147 @@
+use crate::{JsonValue, Node, Tree};
+use std::fmt::Write;
+
+impl JsonValue {
+	// RFC 8785 (JCS) output, suitable for hashing and signing
+	pub fn to_canonical_string(&self) -> String {
+		canonicalize(self)
+	}
+}
+
+fn canonicalize<T: Tree>(value: &T) -> String {
+	enum Step<'v, T> {
+		Value(&'v T),
+		Key(&'v str),
+		Raw(char),
+	}
+
+	let mut out = String::new();
+	let mut stack = vec![Step::Value(value)];
+	while let Some(step) = stack.pop() {
+		let value = match step {
+			Step::Value(value) => value,
+			Step::Key(key) => {
+				write_string(&mut out, key);
+				continue;
+			}
+			Step::Raw(c) => {
+				out.push(c);
+				continue;
+			}
+		};
+
+		match value.node() {
+			Node::Null => out.push_str("null"),
+			Node::Boolean(b) => out.push_str(if b { "true" } else { "false" }),
+			Node::Number(num) => write_number(&mut out, num),
+			Node::String(s) => write_string(&mut out, s),
+			Node::List(ls) => {
+				out.push('[');
+				stack.push(Step::Raw(']'));
+				let values: Vec<_> = ls.collect();
+				for (idx, val) in values.into_iter().enumerate().rev() {
+					stack.push(Step::Value(val));
+					if idx > 0 {
+						stack.push(Step::Raw(','));
+					}
+				}
+			}
+			Node::Object(obj) => {
+				out.push('{');
+				stack.push(Step::Raw('}'));
+				let mut entries: Vec<_> = obj.collect();
+				entries.sort_by(|(l, _), (r, _)| l.encode_utf16().cmp(r.encode_utf16()));
+				for (idx, (key, val)) in entries.into_iter().enumerate().rev() {
+					stack.extend([Step::Value(val), Step::Raw(':'), Step::Key(key)]);
+					if idx > 0 {
+						stack.push(Step::Raw(','));
+					}
+				}
+			}
+		}
+	}
+	out
+}
+
+fn write_string(out: &mut String, s: &str) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			'\x08' => out.push_str("\\b"),
+			'\x0c' => out.push_str("\\f"),
+			'\0'..='\x1f' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+// ECMAScript's Number.prototype.toString, which Rust's shortest round-trip digits line up with
+fn write_number(out: &mut String, num: f64) {
+	if num == 0. {
+		out.push('0');
+		return;
+	}
+	if num < 0. {
+		out.push('-');
+	}
+
+	let sci = format!("{:e}", num.abs());
+	let (mantissa, exp) = sci.split_once('e').unwrap();
+	let mut digits = mantissa.replace('.', "");
+
+	// When two shortest candidates are equally close, Rust picks the upper one but ECMAScript
+	// picks the even one
+	if let Some(last @ (b'1' | b'3' | b'5' | b'7' | b'9')) = mantissa.bytes().last() {
+		let lower = format!("{}{}", &mantissa[..mantissa.len() - 1], (last - 1) as char);
+		if format!("{lower}e{exp}").parse() == Ok(num.abs()) {
+			let exact = format!("{:.800e}", num.abs());
+			let (exact_mantissa, exact_exp) = exact.split_once('e').unwrap();
+			let lower_digits = lower.replace('.', "");
+			if exact_exp == exp
+				&& exact_mantissa.replace('.', "").trim_end_matches('0')
+					== lower_digits.clone() + "5"
+			{
+				digits = lower_digits;
+			}
+		}
+	}
+	let len = digits.len() as i32;
+	let point = exp.parse::<i32>().unwrap() + 1;
+
+	match point {
+		_ if len <= point && point <= 21 => {
+			out.push_str(&digits);
+			out.extend(std::iter::repeat_n('0', (point - len) as usize));
+		}
+		1..=21 => {
+			out.push_str(&digits[..point as usize]);
+			out.push('.');
+			out.push_str(&digits[point as usize..]);
+		}
+		-5..=0 => {
+			out.push_str("0.");
+			out.extend(std::iter::repeat_n('0', -point as usize));
+			out.push_str(&digits);
+		}
+		_ => {
+			out.push_str(&digits[..1]);
+			if len > 1 {
+				out.push('.');
+				out.push_str(&digits[1..]);
+			}
+			write!(
+				out,
+				"e{}{}",
+				if point > 0 { "+" } else { "-" },
+				(point - 1).abs()
+			)
+			.unwrap();
+		}
+	}
+}
@@ -0,0 +1,300 @@
+use crate::{Error, JsonValue, Limit, ParseError, ParseErrorKind, ParseLimits};
+
+pub fn to_vec(value: &JsonValue) -> Vec<u8> {
+	enum Item<'v> {
+		Value(&'v JsonValue),
+		Key(&'v str),
+	}
+
+	let mut out = vec![];
+	let mut stack = vec![Item::Value(value)];
+	while let Some(item) = stack.pop() {
+		let value = match item {
+			Item::Value(value) => value,
+			Item::Key(key) => {
+				write_head(&mut out, 3, key.len() as u64);
+				out.extend_from_slice(key.as_bytes());
+				continue;
+			}
+		};
+
+		match value {
+			JsonValue::Null => out.push(0xf6),
+			JsonValue::Boolean(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+			JsonValue::Number(num) => write_number(&mut out, num.into()),
+			JsonValue::String(s) => {
+				write_head(&mut out, 3, s.len() as u64);
+				out.extend_from_slice(s.as_bytes());
+			}
+			JsonValue::List(ls) => {
+				write_head(&mut out, 4, ls.len() as u64);
+				stack.extend(ls.iter().rev().map(Item::Value));
+			}
+			JsonValue::Object(obj) => {
+				write_head(&mut out, 5, obj.len() as u64);
+				for (key, val) in obj {
+					stack.extend([Item::Value(val), Item::Key(key)]);
+				}
+			}
+		}
+	}
+	out
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+	let major = major << 5;
+	match arg {
+		0..24 => out.push(major | arg as u8),
+		24..0x100 => out.extend([major | 24, arg as u8]),
+		0x100..0x10000 => {
+			out.push(major | 25);
+			out.extend((arg as u16).to_be_bytes());
+		}
+		0x10000..0x1_0000_0000 => {
+			out.push(major | 26);
+			out.extend((arg as u32).to_be_bytes());
+		}
+		_ => {
+			out.push(major | 27);
+			out.extend(arg.to_be_bytes());
+		}
+	}
+}
+
+// Integers where possible, otherwise the narrowest float that holds the value exactly
+fn write_number(out: &mut Vec<u8>, num: f64) {
+	const LIMIT: f64 = 18446744073709551616.;
+	let is_negative_zero = num == 0. && num.is_sign_negative();
+	if num.fract() == 0. && !is_negative_zero {
+		if (0. ..LIMIT).contains(&num) {
+			return write_head(out, 0, num as u64);
+		}
+		if (-LIMIT..0.).contains(&num) {
+			return write_head(out, 1, ((-num) as u128 - 1) as u64);
+		}
+	}
+
+	let single = num as f32;
+	if single as f64 != num {
+		out.push(0xfb);
+		out.extend(num.to_bits().to_be_bytes());
+	} else if let Some(half) = to_half(single) {
+		out.push(0xf9);
+		out.extend(half.to_be_bytes());
+	} else {
+		out.push(0xfa);
+		out.extend(single.to_bits().to_be_bytes());
+	}
+}
+
+fn to_half(num: f32) -> Option<u16> {
+	let bits = num.to_bits();
+	let sign = (bits >> 16) as u16 & 0x8000;
+	let exp = ((bits >> 23) & 0xff) as i32 - 127;
+	let mantissa = bits & 0x7fffff;
+
+	match exp {
+		_ if num == 0. => Some(sign),
+		-14..=15 if mantissa & 0x1fff == 0 => {
+			Some(sign | ((exp + 15) as u16) << 10 | (mantissa >> 13) as u16)
+		}
+		-24..-14 => {
+			let shift = -(exp + 1);
+			let mantissa = mantissa | 0x800000;
+			(mantissa & ((1 << shift) - 1) == 0).then_some(sign | (mantissa >> shift) as u16)
+		}
+		_ => None,
+	}
+}
+
+fn from_half(half: u16) -> f64 {
+	let exp = (half >> 10) & 0x1f;
+	let mantissa = (half & 0x3ff) as f64;
+	let num = match exp {
+		0 => mantissa * 2_f64.powi(-24),
+		31 => f64::NAN,
+		_ => (mantissa + 1024.) * 2_f64.powi(exp as i32 - 25),
+	};
+	if half & 0x8000 != 0 { -num } else { num }
+}
+
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue, Error> {
+	from_slice_with(bytes, &ParseLimits::default())
+}
+
+pub fn from_slice_with(bytes: &[u8], limits: &ParseLimits) -> Result<JsonValue, Error> {
+	struct Container {
+		value: JsonValue,
+		// Items left to read, or None if the length is indefinite
+		remaining: Option<u64>,
+		key: Option<String>,
+	}
+
+	if bytes.len() > limits.max_bytes {
+		return Err(ParseError {
+			kind: Limit::Bytes.into(),
+			offset: limits.max_bytes,
+		}
+		.into());
+	}
+
+	let mut i = 0;
+	let result = (|| -> Result<JsonValue, ParseErrorKind> {
+		let mut stack: Vec<Container> = vec![];
+		let mut start = 0;
+		loop {
+			let next = match stack.last() {
+				Some(Container {
+					remaining: Some(0), ..
+				}) => stack.pop().unwrap().value,
+				Some(Container {
+					remaining: None,
+					key: None,
+					..
+				}) if bytes.get(i) == Some(&0xff) => {
+					i += 1;
+					stack.pop().unwrap().value
+				}
+				_ => {
+					start = i;
+					let initial = *bytes.get(i).ok_or("unexpected end of input")?;
+					let (major, info) = (initial >> 5, initial & 0x1f);
+					i += 1;
+					let arg = read_arg(bytes, &mut i, info)?;
+
+					match (major, arg) {
+						(0, Some(num)) => JsonValue::from(num as f64),
+						(1, Some(num)) => JsonValue::from((-1 - num as i128) as f64),
+						(2, _) => Err("byte strings are not supported")?,
+						(3, len) => JsonValue::from(read_text(bytes, &mut i, len, limits)?),
+						(4 | 5, len) => {
+							if stack.len() >= limits.max_depth {
+								Err(Limit::Depth)?
+							}
+							let is_object = major == 5;
+							stack.push(Container {
+								value: if is_object {
+									JsonValue::object()
+								} else {
+									JsonValue::list()
+								},
+								remaining: match len {
+									Some(len) if is_object => {
+										Some(len.checked_mul(2).ok_or("invalid length")?)
+									}
+									len => len,
+								},
+								key: None,
+							});
+							continue;
+						}
+						// Tags are ignored and only their content is kept
+						(6, Some(_)) => continue,
+						(7, Some(arg)) => match info {
+							20 => JsonValue::Boolean(false),
+							21 => JsonValue::Boolean(true),
+							22 | 23 => JsonValue::Null,
+							25 => JsonValue::from(from_half(arg as u16)),
+							26 => JsonValue::from(f32::from_bits(arg as u32) as f64),
+							27 => JsonValue::from(f64::from_bits(arg)),
+							_ => Err("unsupported simple value")?,
+						},
+						(7, None) => Err("unexpected break")?,
+						_ => Err("invalid indefinite length")?,
+					}
+				}
+			};
+
+			let Some(parent) = stack.last_mut() else {
+				return match i == bytes.len() {
+					true => Ok(next),
+					false => Err("unexpected trailing bytes".into()),
+				};
+			};
+			if let Some(remaining) = &mut parent.remaining {
+				*remaining -= 1;
+			}
+			match (&mut parent.value, parent.key.take()) {
+				(JsonValue::List(ls), _) if ls.len() >= limits.max_entries => Err(Limit::Entries)?,
+				(JsonValue::List(ls), _) => ls.push(next),
+				(JsonValue::Object(_), None) => match String::try_from(next) {
+					Ok(key) => parent.key = Some(key),
+					Err(_) => {
+						i = start;
+						Err("map keys must be strings")?
+					}
+				},
+				(JsonValue::Object(obj), Some(_)) if obj.len() >= limits.max_entries => {
+					Err(Limit::Entries)?
+				}
+				(JsonValue::Object(obj), Some(key)) => {
+					obj.insert(key, next);
+				}
+				_ => unreachable!(),
+			}
+		}
+	})();
+
+	result.map_err(|kind| ParseError { kind, offset: i }.into())
+}
+
+// Returns None for an indefinite length
+fn read_arg(bytes: &[u8], i: &mut usize, info: u8) -> Result<Option<u64>, ParseErrorKind> {
+	let len = match info {
+		0..24 => return Ok(Some(info as u64)),
+		24 => 1,
+		25 => 2,
+		26 => 4,
+		27 => 8,
+		31 => return Ok(None),
+		_ => Err("invalid additional information")?,
+	};
+
+	let arg = bytes.get(*i..*i + len).ok_or("unexpected end of input")?;
+	*i += len;
+	Ok(Some(arg.iter().fold(0, |acc, &b| acc << 8 | b as u64)))
+}
+
+fn read_text(
+	bytes: &[u8],
+	i: &mut usize,
+	len: Option<u64>,
+	limits: &ParseLimits,
+) -> Result<String, ParseErrorKind> {
+	let read_chunk = |i: &mut usize, len: u64| -> Result<&str, ParseErrorKind> {
+		let len = usize::try_from(len).map_err(|_| "invalid length")?;
+		let end = i.checked_add(len).ok_or("invalid length")?;
+		let chunk = bytes.get(*i..end).ok_or("unexpected end of input")?;
+		let chunk = std::str::from_utf8(chunk).map_err(|_| "invalid UTF-8")?;
+		*i = end;
+		Ok(chunk)
+	};
+
+	let Some(len) = len else {
+		// Indefinite-length strings are made of definite-length chunks
+		let mut s = String::new();
+		loop {
+			match bytes.get(*i) {
+				Some(0xff) => break,
+				Some(&initial) if initial >> 5 == 3 => {
+					*i += 1;
+					let len =
+						read_arg(bytes, i, initial & 0x1f)?.ok_or("nested indefinite string")?;
+					s.push_str(read_chunk(i, len)?);
+					if s.len() > limits.max_string_len {
+						Err(Limit::StringLength)?
+					}
+				}
+				Some(_) => Err("invalid string chunk")?,
+				None => Err("unexpected end of input")?,
+			}
+		}
+		*i += 1;
+		return Ok(s);
+	};
+
+	if len > limits.max_string_len as u64 {
+		Err(Limit::StringLength)?
+	}
+	read_chunk(i, len).map(String::from)
+}
@@ -0,0 +1,217 @@
+use crate::{HashMap, JsonValue};
+use std::borrow::{Borrow, Cow};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::sync::LazyLock;
+
+const INLINE_LEN: usize = 22;
+
+// The string inside JsonValue::String. Strings of up to 22 bytes are stored inline, and longer
+// ones in a boxed str without spare capacity, which keeps it at 24 bytes.
+#[derive(Clone)]
+pub struct JsonString(Repr);
+
+#[derive(Clone)]
+enum Repr {
+	Inline(u8, [u8; INLINE_LEN]),
+	Heap(Box<str>),
+}
+
+// The object inside JsonValue::Object. An empty object holds no HashMap, so it costs neither
+// an allocation nor a RandomState until the first mutable access. The map is boxed so that
+// JsonValue is sized by its strings and lists (32 bytes) instead of by a HashMap (56 bytes).
+#[derive(Default)]
+#[allow(clippy::box_collection)]
+pub struct JsonObject(pub(crate) Option<Box<HashMap<String, JsonValue>>>);
+
+static EMPTY_OBJECT: LazyLock<HashMap<String, JsonValue>> = LazyLock::new(HashMap::new);
+
+impl JsonString {
+	pub fn new() -> Self {
+		JsonString(Repr::Inline(0, [0; INLINE_LEN]))
+	}
+
+	pub fn as_str(&self) -> &str {
+		match &self.0 {
+			// SAFETY: only ever filled from a whole str
+			Repr::Inline(len, buf) => unsafe {
+				std::str::from_utf8_unchecked(&buf[..*len as usize])
+			},
+			Repr::Heap(s) => s,
+		}
+	}
+
+	pub fn is_inline(&self) -> bool {
+		matches!(self.0, Repr::Inline(..))
+	}
+
+	fn inline(s: &str) -> Option<Self> {
+		let mut buf = [0; INLINE_LEN];
+		buf.get_mut(..s.len())?.copy_from_slice(s.as_bytes());
+		Some(JsonString(Repr::Inline(s.len() as u8, buf)))
+	}
+}
+
+impl JsonObject {
+	pub fn new() -> Self {
+		JsonObject(None)
+	}
+}
+
+impl Default for JsonString {
+	fn default() -> Self {
+		JsonString::new()
+	}
+}
+
+impl Deref for JsonString {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl Deref for JsonObject {
+	type Target = HashMap<String, JsonValue>;
+
+	fn deref(&self) -> &Self::Target {
+		self.0.as_deref().unwrap_or(&EMPTY_OBJECT)
+	}
+}
+
+impl DerefMut for JsonObject {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.0.get_or_insert_with(Default::default)
+	}
+}
+
+impl From<&str> for JsonString {
+	fn from(s: &str) -> Self {
+		JsonString::inline(s).unwrap_or_else(|| JsonString(Repr::Heap(s.into())))
+	}
+}
+
+impl From<String> for JsonString {
+	fn from(s: String) -> Self {
+		JsonString::inline(&s).unwrap_or_else(|| JsonString(Repr::Heap(s.into_boxed_str())))
+	}
+}
+
+impl From<Cow<'_, str>> for JsonString {
+	fn from(s: Cow<str>) -> Self {
+		match s {
+			Cow::Borrowed(s) => s.into(),
+			Cow::Owned(s) => s.into(),
+		}
+	}
+}
+
+impl From<JsonString> for String {
+	fn from(s: JsonString) -> Self {
+		match s.0 {
+			Repr::Inline(..) => s.as_str().to_owned(),
+			Repr::Heap(s) => s.into_string(),
+		}
+	}
+}
+
+impl<'a> From<&'a JsonString> for &'a str {
+	fn from(s: &'a JsonString) -> Self {
+		s.as_str()
+	}
+}
+
+// Keeps a preallocated map even if it's still empty, since it's about to be filled
+impl From<HashMap<String, JsonValue>> for JsonObject {
+	fn from(map: HashMap<String, JsonValue>) -> Self {
+		JsonObject((map.capacity() > 0).then(|| Box::new(map)))
+	}
+}
+
+impl From<JsonObject> for HashMap<String, JsonValue> {
+	fn from(obj: JsonObject) -> Self {
+		obj.0.map(|map| *map).unwrap_or_default()
+	}
+}
+
+impl<'a> From<&'a JsonObject> for &'a HashMap<String, JsonValue> {
+	fn from(obj: &'a JsonObject) -> Self {
+		obj
+	}
+}
+
+impl<'a> From<&'a mut JsonObject> for &'a mut HashMap<String, JsonValue> {
+	fn from(obj: &'a mut JsonObject) -> Self {
+		obj
+	}
+}
+
+impl<'a> IntoIterator for &'a JsonObject {
+	type Item = (&'a String, &'a JsonValue);
+	type IntoIter = std::collections::hash_map::Iter<'a, String, JsonValue>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a mut JsonObject {
+	type Item = (&'a String, &'a mut JsonValue);
+	type IntoIter = std::collections::hash_map::IterMut<'a, String, JsonValue>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.iter_mut()
+	}
+}
+
+impl AsRef<str> for JsonString {
+	fn as_ref(&self) -> &str {
+		self
+	}
+}
+
+impl Borrow<str> for JsonString {
+	fn borrow(&self) -> &str {
+		self
+	}
+}
+
+impl PartialEq for JsonString {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.as_str() == rhs.as_str()
+	}
+}
+
+impl Eq for JsonString {}
+
+impl PartialEq<str> for JsonString {
+	fn eq(&self, rhs: &str) -> bool {
+		self.as_str() == rhs
+	}
+}
+
+impl PartialEq<&str> for JsonString {
+	fn eq(&self, rhs: &&str) -> bool {
+		self.as_str() == *rhs
+	}
+}
+
+impl Hash for JsonString {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.as_str().hash(state)
+	}
+}
+
+impl Debug for JsonString {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		Debug::fmt(self.as_str(), f)
+	}
+}
+
+impl Display for JsonString {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		f.write_str(self)
+	}
+}
@@ -0,0 +1,217 @@
+use crate::JsonKind;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+	Depth,
+	Bytes,
+	StringLength,
+	Entries,
+	NumberLength,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeError {
+	pub expected: JsonKind,
+	pub found: JsonKind,
+	pub path: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+	Syntax { message: String, offset: usize },
+	DuplicateKey { key: String, offset: usize },
+	LimitExceeded { limit: Limit, offset: usize },
+	Type(TypeError),
+	NumberRange(f64),
+	Encode(String),
+	Io(io::Error),
+	Line { line: usize, source: Box<Error> },
+}
+
+pub(crate) enum ParseErrorKind {
+	Syntax(String),
+	LimitExceeded(Limit),
+	DuplicateKey(String),
+}
+
+pub(crate) struct ParseError {
+	pub kind: ParseErrorKind,
+	pub offset: usize,
+}
+
+impl TypeError {
+	pub fn new(expected: JsonKind, found: JsonKind) -> Self {
+		TypeError {
+			expected,
+			found,
+			path: None,
+		}
+	}
+
+	pub fn with_path(mut self, path: impl Into<String>) -> Self {
+		self.path = Some(path.into());
+		self
+	}
+}
+
+impl Error {
+	pub fn offset(&self) -> Option<usize> {
+		match self {
+			Error::Syntax { offset, .. }
+			| Error::DuplicateKey { offset, .. }
+			| Error::LimitExceeded { offset, .. } => Some(*offset),
+			Error::Line { source, .. } => source.offset(),
+			_ => None,
+		}
+	}
+}
+
+impl From<&str> for ParseErrorKind {
+	fn from(message: &str) -> Self {
+		ParseErrorKind::Syntax(message.to_owned())
+	}
+}
+
+impl From<String> for ParseErrorKind {
+	fn from(message: String) -> Self {
+		ParseErrorKind::Syntax(message)
+	}
+}
+
+impl From<Limit> for ParseErrorKind {
+	fn from(limit: Limit) -> Self {
+		ParseErrorKind::LimitExceeded(limit)
+	}
+}
+
+impl From<ParseError> for Error {
+	fn from(ParseError { kind, offset }: ParseError) -> Self {
+		match kind {
+			ParseErrorKind::Syntax(message) => Error::Syntax { message, offset },
+			ParseErrorKind::DuplicateKey(key) => Error::DuplicateKey { key, offset },
+			ParseErrorKind::LimitExceeded(limit) => Error::LimitExceeded { limit, offset },
+		}
+	}
+}
+
+impl From<TypeError> for Error {
+	fn from(err: TypeError) -> Self {
+		Error::Type(err)
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
+impl PartialEq for Error {
+	fn eq(&self, rhs: &Self) -> bool {
+		match (self, rhs) {
+			(
+				Error::Syntax {
+					message: m1,
+					offset: o1,
+				},
+				Error::Syntax {
+					message: m2,
+					offset: o2,
+				},
+			) => m1 == m2 && o1 == o2,
+			(
+				Error::DuplicateKey {
+					key: k1,
+					offset: o1,
+				},
+				Error::DuplicateKey {
+					key: k2,
+					offset: o2,
+				},
+			) => k1 == k2 && o1 == o2,
+			(
+				Error::LimitExceeded {
+					limit: l1,
+					offset: o1,
+				},
+				Error::LimitExceeded {
+					limit: l2,
+					offset: o2,
+				},
+			) => l1 == l2 && o1 == o2,
+			(Error::Type(l), Error::Type(r)) => l == r,
+			(Error::NumberRange(l), Error::NumberRange(r)) => l.to_bits() == r.to_bits(),
+			(Error::Encode(l), Error::Encode(r)) => l == r,
+			(Error::Io(l), Error::Io(r)) => l.kind() == r.kind(),
+			(
+				Error::Line {
+					line: l1,
+					source: s1,
+				},
+				Error::Line {
+					line: l2,
+					source: s2,
+				},
+			) => l1 == l2 && s1 == s2,
+			_ => false,
+		}
+	}
+}
+
+impl Display for Limit {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		f.write_str(match self {
+			Limit::Depth => "nesting depth",
+			Limit::Bytes => "input size",
+			Limit::StringLength => "string length",
+			Limit::Entries => "container size",
+			Limit::NumberLength => "number length",
+		})
+	}
+}
+
+impl Display for TypeError {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "expected {}, found {}", self.expected, self.found)?;
+		match self.path.as_deref() {
+			// The empty pointer is the whole document
+			Some("") => f.write_str(" at the root"),
+			Some(path) => write!(f, " at {path}"),
+			None => Ok(()),
+		}
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		match self {
+			Error::Syntax { message, offset } => write!(f, "{message} at byte {offset}"),
+			Error::DuplicateKey { key, offset } => {
+				write!(f, "duplicate key {key:?} at byte {offset}")
+			}
+			Error::LimitExceeded { limit, offset } => {
+				write!(f, "maximum {limit} exceeded at byte {offset}")
+			}
+			Error::Type(err) => write!(f, "{err}"),
+			Error::NumberRange(num) => write!(f, "number {num} is out of range"),
+			Error::Encode(message) => write!(f, "cannot encode value: {message}"),
+			Error::Io(err) => write!(f, "I/O error: {err}"),
+			Error::Line { line, source } => write!(f, "line {line}: {source}"),
+		}
+	}
+}
+
+impl std::error::Error for TypeError {}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Type(err) => Some(err),
+			Error::Io(err) => Some(err),
+			Error::Line { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
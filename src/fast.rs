@@ -0,0 +1,508 @@
+use crate::{
+	Builder, Error, JsonKind, JsonValue, Limit, ParseError, ParseErrorKind, ParseLimits,
+	ParseOptions, insert_entry, parse_string, unexpected_char,
+};
+use std::borrow::Cow;
+
+impl JsonValue {
+	// A two-stage parser for large inputs. The first stage finds every structural character,
+	// quote, and start of a scalar 64 bytes at a time, and the second builds the value from that
+	// index without looking at the bytes in between. It accepts the same input as from_str,
+	// except that numbers must follow the JSON grammar exactly.
+	pub fn parse_fast(input: &str) -> Result<JsonValue, Error> {
+		JsonValue::parse_fast_with(input, &ParseOptions::default())
+	}
+
+	pub fn parse_fast_with(input: &str, options: &ParseOptions) -> Result<JsonValue, Error> {
+		parse(input, options, classifier(), &mut ()).map_err(Error::from)
+	}
+}
+
+// One bit per byte of a 64-byte block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Masks {
+	pub(crate) quote: u64,
+	pub(crate) backslash: u64,
+	pub(crate) op: u64,
+	pub(crate) whitespace: u64,
+	// Control characters and DEL, which aren't allowed unescaped in strings
+	pub(crate) control: u64,
+}
+
+pub(crate) type Classify = fn(&[u8; 64]) -> Masks;
+
+#[allow(unreachable_code)]
+pub(crate) fn classifier() -> Classify {
+	#[cfg(target_arch = "x86_64")]
+	return if std::is_x86_feature_detected!("avx2") {
+		// SAFETY: AVX2 support was just checked
+		|block| unsafe { x86::classify_avx2(block) }
+	} else {
+		// SAFETY: SSE2 is part of the x86_64 baseline
+		|block| unsafe { x86::classify_sse2(block) }
+	};
+	classify_swar
+}
+
+const ONES: u64 = 0x0101010101010101;
+const HIGH: u64 = 0x8080808080808080;
+
+// Sets the high bit of each byte of x that equals c
+fn swar_eq(x: u64, c: u8) -> u64 {
+	let y = x ^ (ONES * c as u64);
+	!(((y & !HIGH) + !HIGH) | y) & HIGH
+}
+
+// Gathers the high bit of each byte into the low 8 bits
+fn swar_movemask(m: u64) -> u64 {
+	(m >> 7).wrapping_mul(0x0102040810204080) >> 56
+}
+
+pub(crate) fn classify_swar(block: &[u8; 64]) -> Masks {
+	let mut masks = Masks::default();
+	for (idx, chunk) in block.chunks_exact(8).enumerate() {
+		let x = u64::from_le_bytes(chunk.try_into().unwrap());
+		let below_space = !(((x & !HIGH) + ONES * 0x60) | x) & HIGH;
+		let eq_any = |chars: &[u8]| chars.iter().fold(0, |acc, &c| acc | swar_eq(x, c));
+		let shift = 8 * idx;
+
+		masks.quote |= swar_movemask(swar_eq(x, b'"')) << shift;
+		masks.backslash |= swar_movemask(swar_eq(x, b'\\')) << shift;
+		masks.op |= swar_movemask(eq_any(b"{}[]:,")) << shift;
+		masks.whitespace |= swar_movemask(eq_any(b" \t\n\r")) << shift;
+		masks.control |= swar_movemask(below_space | swar_eq(x, 0x7f)) << shift;
+	}
+	masks
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+	use super::Masks;
+	use std::arch::x86_64::*;
+
+	#[target_feature(enable = "sse2")]
+	pub(super) fn classify_sse2(block: &[u8; 64]) -> Masks {
+		let mut masks = Masks::default();
+		for (idx, chunk) in block.chunks_exact(16).enumerate() {
+			// SAFETY: chunk is 16 bytes long, and the load has no alignment requirement
+			let x = unsafe { _mm_loadu_si128(chunk.as_ptr().cast()) };
+			let eq = |c: u8| _mm_cmpeq_epi8(x, _mm_set1_epi8(c as i8));
+			let eq_any = |chars: &[u8]| {
+				chars
+					.iter()
+					.fold(_mm_setzero_si128(), |acc, &c| _mm_or_si128(acc, eq(c)))
+			};
+			let below_space = _mm_cmpeq_epi8(_mm_min_epu8(x, _mm_set1_epi8(0x1f)), x);
+			let mask = |m| (_mm_movemask_epi8(m) as u16 as u64) << (16 * idx);
+
+			masks.quote |= mask(eq(b'"'));
+			masks.backslash |= mask(eq(b'\\'));
+			masks.op |= mask(eq_any(b"{}[]:,"));
+			masks.whitespace |= mask(eq_any(b" \t\n\r"));
+			masks.control |= mask(_mm_or_si128(below_space, eq(0x7f)));
+		}
+		masks
+	}
+
+	#[target_feature(enable = "avx2")]
+	fn eq(x: __m256i, c: u8) -> __m256i {
+		_mm256_cmpeq_epi8(x, _mm256_set1_epi8(c as i8))
+	}
+
+	#[target_feature(enable = "avx2")]
+	fn eq_any(x: __m256i, chars: &[u8]) -> __m256i {
+		let mut acc = _mm256_setzero_si256();
+		for &c in chars {
+			acc = _mm256_or_si256(acc, eq(x, c));
+		}
+		acc
+	}
+
+	#[target_feature(enable = "avx2")]
+	fn mask(m: __m256i, idx: usize) -> u64 {
+		(_mm256_movemask_epi8(m) as u32 as u64) << (32 * idx)
+	}
+
+	#[target_feature(enable = "avx2")]
+	pub(super) fn classify_avx2(block: &[u8; 64]) -> Masks {
+		let mut masks = Masks::default();
+		for (idx, chunk) in block.chunks_exact(32).enumerate() {
+			// SAFETY: chunk is 32 bytes long, and the load has no alignment requirement
+			let x = unsafe { _mm256_loadu_si256(chunk.as_ptr().cast()) };
+			let below_space = _mm256_cmpeq_epi8(_mm256_min_epu8(x, _mm256_set1_epi8(0x1f)), x);
+
+			masks.quote |= mask(eq(x, b'"'), idx);
+			masks.backslash |= mask(eq(x, b'\\'), idx);
+			masks.op |= mask(eq_any(x, b"{}[]:,"), idx);
+			masks.whitespace |= mask(eq_any(x, b" \t\n\r"), idx);
+			masks.control |= mask(_mm256_or_si256(below_space, eq(x, 0x7f)), idx);
+		}
+		masks
+	}
+}
+
+pub(crate) struct Index {
+	// Offsets of structural characters, quotes, and the first byte of each scalar
+	pub(crate) structurals: Vec<usize>,
+	// Offsets of control characters inside strings
+	pub(crate) controls: Vec<usize>,
+}
+
+pub(crate) fn index(bytes: &[u8], classify: Classify) -> Index {
+	const EVEN_BITS: u64 = 0x5555555555555555;
+
+	let mut index = Index {
+		structurals: Vec::with_capacity(bytes.len() / 4),
+		controls: vec![],
+	};
+	let (mut prev_escaped, mut prev_in_string, mut prev_scalar) = (0, 0, 0);
+
+	for (block_idx, chunk) in bytes.chunks(64).enumerate() {
+		// The last block is padded with whitespace
+		let masks = match chunk.try_into() {
+			Ok(block) => classify(block),
+			Err(_) => {
+				let mut block = [b' '; 64];
+				block[..chunk.len()].copy_from_slice(chunk);
+				classify(&block)
+			}
+		};
+
+		// A backslash escapes the next byte unless it's escaped itself, so odd-length runs of
+		// backslashes escape the byte after them. Adding each run's start to the run carries
+		// past its end, which picks out the runs that start on an odd bit.
+		let backslash = masks.backslash & !prev_escaped;
+		let follows_escape = backslash << 1 | prev_escaped;
+		let odd_starts = backslash & !EVEN_BITS & !follows_escape;
+		let (even_carries, overflow) = odd_starts.overflowing_add(backslash);
+		prev_escaped = overflow as u64;
+		let escaped = (EVEN_BITS ^ (even_carries << 1)) & follows_escape;
+
+		// A prefix XOR sets every bit from an opening quote up to, but not including, its
+		// closing quote
+		let quote = masks.quote & !escaped;
+		let mut in_string = quote;
+		for shift in [1, 2, 4, 8, 16, 32] {
+			in_string ^= in_string << shift;
+		}
+		in_string ^= prev_in_string;
+		prev_in_string = 0_u64.wrapping_sub(in_string >> 63);
+
+		let scalar = !in_string & !quote & !masks.op & !masks.whitespace;
+		let scalar_start = scalar & !(scalar << 1 | prev_scalar);
+		prev_scalar = scalar >> 63;
+
+		let base = 64 * block_idx;
+		push_bits(
+			&mut index.structurals,
+			base,
+			masks.op & !in_string | quote | scalar_start,
+		);
+		push_bits(&mut index.controls, base, masks.control & in_string);
+	}
+	index
+}
+
+fn push_bits(out: &mut Vec<usize>, base: usize, mut bits: u64) {
+	while bits != 0 {
+		out.push(base + bits.trailing_zeros() as usize);
+		bits &= bits - 1;
+	}
+}
+
+// The second stage, as a stream of tokens that a builder can consume without having to know
+// about the index
+pub(crate) enum Token<'a> {
+	StartList,
+	StartObject,
+	End,
+	// The key and the offset of its opening quote
+	Key(Cow<'a, str>, usize),
+	Null,
+	Boolean(bool),
+	Number(f64),
+	String(Cow<'a, str>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Expecting {
+	CommaOrBrace,
+	CommaOrBracket,
+	Key,
+	KeyOrBrace,
+	Value,
+	ValueOrBracket,
+}
+use Expecting::*;
+
+pub(crate) struct Tokens<'a> {
+	input: &'a str,
+	index: Index,
+	pos: usize,
+	// Where the last token ended, or where the error is after next fails
+	pub(crate) offset: usize,
+	expect: Expecting,
+	// Whether each open container is an object
+	containers: Vec<bool>,
+	limits: ParseLimits,
+	done: bool,
+}
+
+impl<'a> Tokens<'a> {
+	pub(crate) fn new(
+		input: &'a str,
+		limits: &ParseLimits,
+		classify: Classify,
+	) -> Result<Self, ParseError> {
+		let error = |kind, offset| Err(ParseError { kind, offset });
+		if input.len() > limits.max_bytes {
+			return error(Limit::Bytes.into(), limits.max_bytes);
+		}
+		// Whitespace is only skipped inside containers, same as from_str
+		if let Some(c @ (b' ' | b'\t' | b'\n' | b'\r')) = input.as_bytes().first() {
+			return error(format!("unexpected character: {}", *c as char).into(), 0);
+		}
+
+		Ok(Tokens {
+			input,
+			index: index(input.as_bytes(), classify),
+			pos: 0,
+			offset: 0,
+			expect: Value,
+			containers: vec![],
+			limits: *limits,
+			done: false,
+		})
+	}
+
+	// None once the root value is complete and nothing follows it
+	pub(crate) fn next(&mut self) -> Result<Option<Token<'a>>, ParseErrorKind> {
+		let (input, bytes) = (self.input, self.input.as_bytes());
+		let limits = &self.limits;
+		if self.done {
+			return match input[self.offset..].chars().next() {
+				Some(c) => Err(format!("unexpected character: {c}").into()),
+				None => Ok(None),
+			};
+		}
+
+		// Scalars have to end at whitespace, a structural character, or a quote
+		let check_end = |i: usize| match bytes.get(i) {
+			None
+			| Some(b' ' | b'\t' | b'\n' | b'\r' | b'{' | b'}' | b'[' | b']' | b':' | b',')
+			| Some(b'"') => Ok(()),
+			Some(_) => Err(unexpected_char(input, i)),
+		};
+
+		let i = &mut self.offset;
+		let token = loop {
+			let Some(&start) = self.index.structurals.get(self.pos) else {
+				*i = bytes.len();
+				Err("unexpected end of input")?
+			};
+			self.pos += 1;
+			*i = start;
+
+			break match (bytes[start], self.expect) {
+				(b'{' | b'[', Value | ValueOrBracket) => {
+					*i += 1;
+					let is_object = bytes[start] == b'{';
+					self.expect = if is_object {
+						KeyOrBrace
+					} else {
+						ValueOrBracket
+					};
+					if self.containers.len() >= limits.max_depth {
+						Err(Limit::Depth)?
+					}
+					self.containers.push(is_object);
+					return Ok(Some(match is_object {
+						true => Token::StartObject,
+						false => Token::StartList,
+					}));
+				}
+				(b'}', CommaOrBrace | KeyOrBrace) | (b']', CommaOrBracket | ValueOrBracket) => {
+					*i += 1;
+					self.containers.pop();
+					Token::End
+				}
+				(b',', CommaOrBracket | CommaOrBrace) => {
+					self.expect = if self.expect == CommaOrBracket {
+						Value
+					} else {
+						Key
+					};
+					continue;
+				}
+				(b'"', Value | ValueOrBracket | Key | KeyOrBrace) => {
+					let Index {
+						structurals,
+						controls,
+					} = &self.index;
+
+					// Strings without escapes or control characters are borrowed straight from
+					// the input, and anything else goes through the regular string parser
+					let end = structurals
+						.get(self.pos)
+						.copied()
+						.filter(|&end| bytes[end] == b'"');
+					let has_control = |end| {
+						let first = controls.partition_point(|&c| c < start);
+						controls.get(first).is_some_and(|&c| c < end)
+					};
+					let s = match end {
+						Some(end) if !has_control(end) && !bytes[start..end].contains(&b'\\') => {
+							*i = end;
+							if end - start - 1 > limits.max_string_len {
+								Err(Limit::StringLength)?
+							}
+							*i = end + 1;
+							self.pos += 1;
+							Cow::Borrowed(&input[start + 1..end])
+						}
+						_ => {
+							let s = parse_string(input, i, limits)?;
+							self.pos += structurals[self.pos..].partition_point(|&p| p < *i);
+							s
+						}
+					};
+
+					if matches!(self.expect, Key | KeyOrBrace) {
+						match structurals.get(self.pos) {
+							Some(&colon) if bytes[colon] == b':' => self.pos += 1,
+							_ => Err("missing colon")?,
+						}
+						self.expect = Value;
+						return Ok(Some(Token::Key(s, start)));
+					}
+
+					Token::String(s)
+				}
+				(b'-' | b'0'..=b'9', Value | ValueOrBracket) => {
+					*i += (bytes[*i] == b'-') as usize;
+					let digits = |i: &mut usize| {
+						let len = bytes[*i..]
+							.iter()
+							.take_while(|c| c.is_ascii_digit())
+							.count();
+						*i += len;
+						match (len, bytes.get(*i)) {
+							(1.., _) => Ok(()),
+							(0, Some(_)) => Err(unexpected_char(input, *i)),
+							(0, None) => Err("unexpected end of input".into()),
+						}
+					};
+
+					if bytes.get(*i) == Some(&b'0')
+						&& bytes.get(*i + 1).is_some_and(u8::is_ascii_digit)
+					{
+						Err("illegal leading zero")?
+					}
+					digits(i)?;
+					if bytes.get(*i) == Some(&b'.') {
+						*i += 1;
+						digits(i)?;
+					}
+					if let Some(b'e' | b'E') = bytes.get(*i) {
+						*i += 1;
+						*i += matches!(bytes.get(*i), Some(b'-' | b'+')) as usize;
+						digits(i)?;
+					}
+
+					if *i - start > limits.max_number_len {
+						Err(Limit::NumberLength)?
+					}
+					check_end(*i)?;
+					Token::Number(input[start..*i].parse().unwrap())
+				}
+				(b't', Value | ValueOrBracket) if bytes.get(*i..*i + 4) == Some(b"true") => {
+					*i += 4;
+					check_end(*i)?;
+					Token::Boolean(true)
+				}
+				(b'f', Value | ValueOrBracket) if bytes.get(*i..*i + 5) == Some(b"false") => {
+					*i += 5;
+					check_end(*i)?;
+					Token::Boolean(false)
+				}
+				(b'n', Value | ValueOrBracket) if bytes.get(*i..*i + 4) == Some(b"null") => {
+					*i += 4;
+					check_end(*i)?;
+					Token::Null
+				}
+				_ => Err(unexpected_char(input, *i))?,
+			};
+		};
+
+		// The token completed a value
+		match self.containers.last() {
+			Some(true) => self.expect = CommaOrBrace,
+			Some(false) => self.expect = CommaOrBracket,
+			None => self.done = true,
+		}
+		Ok(Some(token))
+	}
+}
+
+pub(crate) fn parse<'a, T: Builder<'a>>(
+	input: &'a str,
+	options: &ParseOptions,
+	classify: Classify,
+	keys: &mut T::Keys,
+) -> Result<T, ParseError> {
+	let limits = &options.limits;
+	let mut tokens = Tokens::new(input, limits, classify)?;
+	let mut stack = vec![];
+	let mut key_stack = vec![];
+	let mut collected_keys = vec![];
+	let mut root = None;
+
+	let result = (|| -> Result<T, ParseErrorKind> {
+		while let Some(token) = tokens.next()? {
+			let next = match token {
+				Token::StartList => {
+					stack.push(T::list());
+					continue;
+				}
+				Token::StartObject => {
+					stack.push(T::object());
+					collected_keys.push(Default::default());
+					continue;
+				}
+				Token::Key(key, start) => {
+					key_stack.push((key, start));
+					continue;
+				}
+				Token::End => {
+					let value: T = stack.pop().unwrap();
+					if value.kind() == JsonKind::Object {
+						collected_keys.pop();
+					}
+					value
+				}
+				Token::Null => T::null(),
+				Token::Boolean(b) => T::boolean(b),
+				Token::Number(num) => T::number(num),
+				Token::String(s) => T::string(s),
+			};
+
+			match stack.last_mut() {
+				Some(parent) if parent.entries() >= limits.max_entries => Err(Limit::Entries)?,
+				Some(parent) if parent.kind() != JsonKind::Object => parent.push_value(next),
+				Some(obj) => {
+					let (key, key_start) = key_stack.pop().unwrap();
+					let collected = collected_keys.last_mut().unwrap();
+					insert_entry(obj, keys, key, next, options.duplicate_keys, collected)
+						.inspect_err(|_| tokens.offset = key_start)?;
+				}
+				None => root = Some(next),
+			}
+		}
+		Ok(root.take().unwrap())
+	})();
+
+	result.map_err(|kind| ParseError {
+		kind,
+		offset: tokens.offset,
+	})
+}
@@ -0,0 +1,249 @@
+use crate::fast::{self, classifier};
+use crate::{
+	Builder, Error, FiniteF64, HashMap, JsonString, JsonValue, Node, ParseOptions, Tree,
+	build_from, impl_tree, impl_try_from, impl_try_from_ref, serialize, str_key, tree_eq,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem::take;
+use std::sync::Arc;
+
+// Like JsonValue, except that object keys are shared. Every occurrence of a key in the documents
+// parsed by one KeyInterner points at the same allocation, which saves a lot of memory on arrays
+// of records. Maps are boxed for the same reason JsonObject's are, to keep values at 32 bytes.
+pub enum InternedValue {
+	Null,
+	Boolean(bool),
+	Number(FiniteF64),
+	String(JsonString),
+	List(Vec<InternedValue>),
+	Object(Box<HashMap<Arc<str>, InternedValue>>),
+}
+
+// Keeps every distinct key it has seen alive, so reuse one across documents with the same shape
+// and drop it once they stop coming
+#[derive(Default)]
+pub struct KeyInterner {
+	keys: HashSet<Arc<str>>,
+}
+
+impl KeyInterner {
+	pub fn new() -> Self {
+		KeyInterner::default()
+	}
+
+	pub fn intern(&mut self, key: &str) -> Arc<str> {
+		match self.keys.get(key) {
+			Some(key) => key.clone(),
+			None => {
+				let key: Arc<str> = key.into();
+				self.keys.insert(key.clone());
+				key
+			}
+		}
+	}
+
+	// The number of distinct keys
+	pub fn len(&self) -> usize {
+		self.keys.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.keys.is_empty()
+	}
+
+	// Accepts the same input as parse_fast
+	pub fn parse(&mut self, input: &str) -> Result<InternedValue, Error> {
+		self.parse_with(input, &ParseOptions::default())
+	}
+
+	pub fn parse_with(
+		&mut self,
+		input: &str,
+		options: &ParseOptions,
+	) -> Result<InternedValue, Error> {
+		fast::parse(input, options, classifier(), self).map_err(Error::from)
+	}
+}
+
+impl InternedValue {
+	pub fn as_list(&self) -> Option<&Vec<InternedValue>> {
+		self.try_into().ok()
+	}
+
+	pub fn as_list_mut(&mut self) -> Option<&mut Vec<InternedValue>> {
+		self.try_into().ok()
+	}
+
+	pub fn as_object(&self) -> Option<&HashMap<Arc<str>, InternedValue>> {
+		<&Box<_>>::try_from(self).ok().map(|obj| &**obj)
+	}
+
+	pub fn as_object_mut(&mut self) -> Option<&mut HashMap<Arc<str>, InternedValue>> {
+		<&mut Box<_>>::try_from(self).ok().map(|obj| &mut **obj)
+	}
+
+	pub fn to_value(&self) -> JsonValue {
+		build_from(self)
+	}
+}
+
+impl_tree!(InternedValue);
+
+impl_try_from!(
+	InternedValue;
+	Boolean: bool,
+	String: String,
+	List: Vec<InternedValue>,
+	Object: Box<HashMap<Arc<str>, InternedValue>>
+);
+
+impl_try_from_ref!(
+	InternedValue;
+	InternedValue: Number => f64,
+	&'a InternedValue: Number => f64,
+	&'a mut InternedValue: Number => f64,
+
+	&'a InternedValue: Boolean => &'a bool,
+	&'a mut InternedValue: Boolean => &'a mut bool,
+
+	&'a InternedValue: String => &'a str,
+	&'a mut InternedValue: String => &'a mut JsonString,
+
+	&'a InternedValue: List => &'a Vec<InternedValue>,
+	&'a mut InternedValue: List => &'a mut Vec<InternedValue>,
+
+	&'a InternedValue: Object => &'a Box<HashMap<Arc<str>, InternedValue>>,
+	&'a mut InternedValue: Object => &'a mut Box<HashMap<Arc<str>, InternedValue>>
+);
+
+impl PartialEq for InternedValue {
+	fn eq(&self, rhs: &Self) -> bool {
+		tree_eq(self, rhs)
+	}
+}
+
+impl Eq for InternedValue {}
+
+impl From<InternedValue> for JsonValue {
+	fn from(value: InternedValue) -> Self {
+		value.to_value()
+	}
+}
+
+impl Tree for InternedValue {
+	type ListIter<'v> = std::slice::Iter<'v, InternedValue>;
+	type ObjectIter<'v> = std::iter::Map<
+		std::collections::hash_map::Iter<'v, Arc<str>, InternedValue>,
+		fn((&'v Arc<str>, &'v InternedValue)) -> (&'v str, &'v InternedValue),
+	>;
+
+	fn node(&self) -> Node<'_, Self> {
+		match self {
+			InternedValue::Null => Node::Null,
+			InternedValue::Boolean(b) => Node::Boolean(*b),
+			InternedValue::Number(num) => Node::Number(num.into()),
+			InternedValue::String(s) => Node::String(s),
+			InternedValue::List(ls) => Node::List(ls.iter()),
+			InternedValue::Object(obj) => Node::Object(obj.iter().map(str_key as _)),
+		}
+	}
+
+	fn items(&self) -> Option<&[Self]> {
+		match self {
+			InternedValue::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn items_mut(&mut self) -> Option<&mut [Self]> {
+		match self {
+			InternedValue::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn lookup(&self, key: &str) -> Option<&Self> {
+		match self {
+			InternedValue::Object(obj) => obj.get(key),
+			_ => None,
+		}
+	}
+
+	fn lookup_mut(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			InternedValue::Object(obj) => obj.get_mut(key),
+			_ => None,
+		}
+	}
+
+	fn entry(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			InternedValue::Object(obj) => {
+				Some(obj.entry(key.into()).or_insert(InternedValue::Null))
+			}
+			_ => None,
+		}
+	}
+
+	fn take_children(&mut self, out: &mut Vec<Self>) {
+		match self {
+			InternedValue::List(ls) => out.append(ls),
+			InternedValue::Object(obj) => out.extend(take(&mut **obj).into_values()),
+			_ => {}
+		}
+	}
+}
+
+impl<'a> Builder<'a> for InternedValue {
+	type Keys = KeyInterner;
+
+	fn null() -> Self {
+		InternedValue::Null
+	}
+
+	fn boolean(val: bool) -> Self {
+		InternedValue::Boolean(val)
+	}
+
+	fn number(val: f64) -> Self {
+		FiniteF64::try_from(val).map_or(InternedValue::Null, InternedValue::Number)
+	}
+
+	fn string(val: Cow<'a, str>) -> Self {
+		InternedValue::String(val.into())
+	}
+
+	fn list() -> Self {
+		InternedValue::List(Vec::new())
+	}
+
+	fn object() -> Self {
+		InternedValue::Object(Box::default())
+	}
+
+	fn push_value(&mut self, val: Self) {
+		if let InternedValue::List(ls) = self {
+			ls.push(val);
+		}
+	}
+
+	fn insert_value(&mut self, keys: &mut KeyInterner, key: Cow<'a, str>, val: Self) {
+		if let InternedValue::Object(obj) = self {
+			obj.insert(keys.intern(&key), val);
+		}
+	}
+}
+
+impl Display for InternedValue {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, false)
+	}
+}
+
+impl Debug for InternedValue {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, true)
+	}
+}
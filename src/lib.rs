@@ -1,11 +1,50 @@
+use std::borrow::Cow;
 pub use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::{self, Debug, Display, Formatter, Write};
 use std::iter::repeat_with;
-use std::mem::{forget, replace, take};
-use std::ops::{Index, IndexMut};
+use std::mem::{replace, take};
 use std::str::FromStr;
 
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod arbitrary;
+mod arena;
+mod binary;
+mod borrowed;
+#[cfg(feature = "bson")]
+pub mod bson;
+mod canonical;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+mod compact;
+mod error;
+mod fast;
+mod intern;
+pub mod lines;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+mod shared;
+pub mod stream;
+mod validate;
+mod view;
+mod walk;
+
+pub use arena::{ArenaList, ArenaObject, ArenaValue, JsonArena};
+pub use binary::BinaryOptions;
+pub use borrowed::{JsonValueRef, parse_borrowed};
+pub use compact::{JsonObject, JsonString};
+pub use error::{Error, Limit, TypeError};
+use error::{ParseError, ParseErrorKind};
+pub use intern::{InternedValue, KeyInterner};
+pub use shared::SharedJson;
+#[doc(hidden)]
+pub use validate::validate_literal;
+pub use view::JsonView;
+pub use walk::{JsonPointer, Visitor, Walk, WalkMut};
+
+// The original tests build numbers through the fallible conversions and use 3.14 as sample data
 #[cfg(test)]
+#[allow(clippy::unnecessary_fallible_conversions, clippy::approx_constant)]
 mod tests;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -15,73 +54,231 @@ pub enum JsonValue {
 	Null,
 	Boolean(bool),
 	Number(FiniteF64),
-	String(String),
+	String(JsonString),
 	List(Vec<JsonValue>),
-	Object(HashMap<String, JsonValue>),
+	Object(JsonObject),
 }
 
+// Values may be any expression that converts into a JsonValue. Object keys are a single token
+// (usually a string literal) or a `[computed]` expression, and `..other` merges another object.
+//
+/// ```compile_fail
+/// microjson::json!({"missing colon" 1});
+/// ```
+///
+/// ```compile_fail
+/// microjson::json!({"missing value":});
+/// ```
+///
+/// ```compile_fail
+/// microjson::json!([1,, 2]);
+/// ```
+///
+/// ```compile_fail
+/// microjson::json!({"a": 1 2});
+/// ```
+///
+/// ```compile_fail
+/// microjson::json!({..vec![1]});
+/// ```
 #[macro_export]
 macro_rules! json {
+	($($json:tt)+) => {
+		$crate::json_internal!($($json)+)
+	};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+	(@list [$($elems:expr,)*]) => {
+		::std::vec![$($elems,)*]
+	};
+	(@list [$($elems:expr),*]) => {
+		::std::vec![$($elems),*]
+	};
+	(@list [$($elems:expr,)*] null $($rest:tt)*) => {
+		$crate::json_internal!(@list [$($elems,)* $crate::JsonValue::Null] $($rest)*)
+	};
+	(@list [$($elems:expr,)*] [$($list:tt)*] $($rest:tt)*) => {
+		$crate::json_internal!(@list [$($elems,)* $crate::json_internal!([$($list)*])] $($rest)*)
+	};
+	(@list [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+		$crate::json_internal!(@list [$($elems,)* $crate::json_internal!({$($object)*})] $($rest)*)
+	};
+	(@list [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+		$crate::json_internal!(@list [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+	};
+	(@list [$($elems:expr,)*] $last:expr) => {
+		$crate::json_internal!(@list [$($elems,)* $crate::json_internal!($last)])
+	};
+	(@list [$($elems:expr),*] , $($rest:tt)*) => {
+		$crate::json_internal!(@list [$($elems,)*] $($rest)*)
+	};
+	(@list [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+		$crate::json_unexpected!($unexpected)
+	};
+
+	(@object $object:ident ()) => {};
+	(@object $object:ident (.. $other:expr , $($rest:tt)*)) => {
+		$crate::json_internal!(@spread $object ($other));
+		$crate::json_internal!(@object $object ($($rest)*));
+	};
+	(@object $object:ident (.. $other:expr)) => {
+		$crate::json_internal!(@spread $object ($other));
+	};
+	(@object $object:ident (, $($rest:tt)*)) => {
+		$crate::json_unexpected!(,)
+	};
+	(@object $object:ident ([$key:expr] : $($rest:tt)*)) => {
+		$crate::json_internal!(@value $object ($key) ($($rest)*));
+	};
+	(@object $object:ident ($key:tt : $($rest:tt)*)) => {
+		$crate::json_internal!(@value $object ($key) ($($rest)*));
+	};
+	(@object $object:ident ($key:tt)) => {
+		::std::compile_error!("expected `:` after object key")
+	};
+	(@object $object:ident ($key:tt $unexpected:tt $($rest:tt)*)) => {
+		$crate::json_unexpected!($unexpected)
+	};
+
+	(@value $object:ident ($key:expr) ()) => {
+		::std::compile_error!("expected a value after `:`")
+	};
+	(@value $object:ident ($key:expr) (null $($rest:tt)*)) => {
+		$crate::json_internal!(@insert $object ($key) ($crate::JsonValue::Null) ($($rest)*));
+	};
+	(@value $object:ident ($key:expr) ([$($list:tt)*] $($rest:tt)*)) => {
+		$crate::json_internal!(@insert $object ($key) ($crate::json_internal!([$($list)*])) ($($rest)*));
+	};
+	(@value $object:ident ($key:expr) ({$($inner:tt)*} $($rest:tt)*)) => {
+		$crate::json_internal!(@insert $object ($key) ($crate::json_internal!({$($inner)*})) ($($rest)*));
+	};
+	(@value $object:ident ($key:expr) ($value:expr , $($rest:tt)*)) => {
+		$crate::json_internal!(@insert $object ($key) ($crate::json_internal!($value)) (, $($rest)*));
+	};
+	(@value $object:ident ($key:expr) ($value:expr)) => {
+		$crate::json_internal!(@insert $object ($key) ($crate::json_internal!($value)) ());
+	};
+
+	(@insert $object:ident ($key:expr) ($value:expr) ()) => {
+		$object.insert(::std::string::ToString::to_string(&$key), $value);
+	};
+	(@insert $object:ident ($key:expr) ($value:expr) (, $($rest:tt)*)) => {
+		$object.insert(::std::string::ToString::to_string(&$key), $value);
+		$crate::json_internal!(@object $object ($($rest)*));
+	};
+	(@insert $object:ident ($key:expr) ($value:expr) ($unexpected:tt $($rest:tt)*)) => {
+		$crate::json_unexpected!($unexpected)
+	};
+
+	(@spread $object:ident ($other:expr)) => {
+		::std::iter::Extend::extend(&mut $object, $crate::JsonSpread::into_object($other));
+	};
+
+	(@key [$key:expr]) => {
+		$key
+	};
+	(@key $key:tt) => {
+		$key
+	};
+
 	(null) => {
-		JsonValue::Null
+		$crate::JsonValue::Null
+	};
+	([]) => {
+		$crate::JsonValue::List(::std::vec![])
 	};
-	([$($elem:tt),*]) => {
-		JsonValue::List(vec![$(json!($elem)),*])
+	// Lists and objects whose values are single tokens are expanded in one step, so their size
+	// isn't bounded by the recursion limit. Anything else goes through the muncher above.
+	([$($elem:tt),+ $(,)?]) => {
+		$crate::JsonValue::List(::std::vec![$($crate::json_internal!($elem)),+])
 	};
-	({$($key:tt : $val:tt),*}) => {
-		JsonValue::Object(vec![
-			$(($key.to_string(), json!($val))),*
-		].into_iter().collect())
+	({$($key:tt : $value:tt),+ $(,)?}) => {
+		$crate::JsonValue::Object(<$crate::JsonObject as ::std::convert::From<_>>::from({
+			let mut object = $crate::HashMap::new();
+			$(
+				object.insert(
+					::std::string::ToString::to_string(&$crate::json_internal!(@key $key)),
+					$crate::json_internal!($value),
+				);
+			)+
+			object
+		}))
 	};
-	($e:expr) => {
-		JsonValue::from($e)
+	([$($list:tt)+]) => {
+		$crate::JsonValue::List($crate::json_internal!(@list [] $($list)+))
+	};
+	({}) => {
+		$crate::JsonValue::object()
+	};
+	({$($object:tt)+}) => {
+		$crate::JsonValue::Object(<$crate::JsonObject as ::std::convert::From<_>>::from({
+			let mut object = $crate::HashMap::new();
+			$crate::json_internal!(@object object ($($object)+));
+			object
+		}))
+	};
+	($other:expr) => {
+		<$crate::JsonValue as ::std::convert::From<_>>::from($other)
+	};
+}
+
+// Matches no tokens, so the compiler points at the unexpected one
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_unexpected {
+	() => {};
+}
+
+// Like json!, but takes a JSON string literal that is checked at compile time
+//
+/// ```compile_fail,E0080
+/// microjson::json_str!(r#"{"trailing": "comma",}"#);
+/// ```
+#[macro_export]
+macro_rules! json_str {
+	($json:literal) => {{
+		const _: () = match $crate::validate_literal($json) {
+			::std::result::Result::Ok(()) => {}
+			::std::result::Result::Err(err) => ::std::panic!("{}", err),
+		};
+		<$crate::JsonValue as ::std::str::FromStr>::from_str($json)
+			.expect("literal was validated at compile time")
+	}};
+}
+
+// What json! accepts after `..`. Maps are checked when compiling, but a JsonValue can only be
+// checked once it's known to be an object.
+#[doc(hidden)]
+pub trait JsonSpread {
+	fn into_object(self) -> HashMap<String, JsonValue>;
+}
+
+impl JsonSpread for JsonValue {
+	fn into_object(self) -> HashMap<String, JsonValue> {
+		let kind = self.kind();
+		HashMap::try_from(self)
+			.unwrap_or_else(|_| panic!("json! can only spread objects, found {kind}"))
 	}
 }
 
-impl Drop for JsonValue {
-	fn drop(&mut self) {
-		if matches!(self, JsonValue::List(_) | JsonValue::Object(_)) {
-			let mut stack = vec![replace(self, JsonValue::Null)];
-			while let Some(mut last) = stack.pop() {
-				match &mut last {
-					JsonValue::List(lst) => stack.extend(take(lst)),
-					JsonValue::Object(obj) => stack.extend(take(obj).into_values()),
-					_ => continue,
-				};
-				forget(last);
-			}
-		}
+impl JsonSpread for JsonObject {
+	fn into_object(self) -> HashMap<String, JsonValue> {
+		self.into()
+	}
+}
+
+impl JsonSpread for HashMap<String, JsonValue> {
+	fn into_object(self) -> HashMap<String, JsonValue> {
+		self
 	}
 }
 
 impl PartialEq for JsonValue {
 	fn eq(&self, rhs: &Self) -> bool {
-		let mut lhs_stack = vec![self];
-		let mut rhs_stack = vec![rhs];
-
-		while let (Some(lhs), Some(rhs)) = (lhs_stack.pop(), rhs_stack.pop()) {
-			match (lhs, rhs) {
-				(JsonValue::List(l), JsonValue::List(r)) if l.len() == r.len() => {
-					lhs_stack.extend(l);
-					rhs_stack.extend(r);
-				}
-				(JsonValue::Object(l), JsonValue::Object(r)) if l.len() == r.len() => {
-					for (key, val1) in l {
-						let Some(val2) = r.get(key) else {
-							return false;
-						};
-						lhs_stack.push(val1);
-						rhs_stack.push(val2);
-					}
-				}
-				(JsonValue::Boolean(l), JsonValue::Boolean(r)) if l == r => {}
-				(JsonValue::Number(l), JsonValue::Number(r)) if l == r => {}
-				(JsonValue::String(l), JsonValue::String(r)) if l == r => {}
-				(JsonValue::Null, JsonValue::Null) => {}
-				_ => return false,
-			}
-		}
-		true
+		tree_eq(self, rhs)
 	}
 }
 
@@ -95,13 +292,36 @@ impl Clone for JsonValue {
 }
 
 impl TryFrom<f64> for FiniteF64 {
-	type Error = &'static str;
+	type Error = Error;
 
 	fn try_from(value: f64) -> Result<Self, Self::Error> {
 		value
 			.is_finite()
 			.then_some(FiniteF64(value))
-			.ok_or("value is not a finite number")
+			.ok_or(Error::NumberRange(value))
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JsonKind {
+	Null,
+	Boolean,
+	Number,
+	String,
+	List,
+	Object,
+}
+
+impl Display for JsonKind {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		f.write_str(match self {
+			JsonKind::Null => "null",
+			JsonKind::Boolean => "boolean",
+			JsonKind::Number => "number",
+			JsonKind::String => "string",
+			JsonKind::List => "list",
+			JsonKind::Object => "object",
+		})
 	}
 }
 
@@ -121,48 +341,53 @@ impl_from!(
 	u32 => JsonValue: val => FiniteF64::try_from(val as f64).map(JsonValue::from).unwrap(),
 	i32 => JsonValue: val => FiniteF64::try_from(val as f64).map(JsonValue::from).unwrap(),
 	FiniteF64 => JsonValue: val => JsonValue::Number(val),
-	&str => JsonValue: val => JsonValue::String(val.to_owned()),
-	String => JsonValue: val => JsonValue::String(val),
+	&str => JsonValue: val => JsonValue::String(val.into()),
+	String => JsonValue: val => JsonValue::String(val.into()),
 	Vec<JsonValue> => JsonValue: val => JsonValue::List(val),
-	HashMap<String, JsonValue> => JsonValue: val => JsonValue::Object(val),
+	HashMap<String, JsonValue> => JsonValue: val => JsonValue::Object(val.into()),
 	FiniteF64 => f64: val => val.0,
 	&'a FiniteF64 => f64: val => val.0,
 	&'a mut FiniteF64 => f64: val => val.0
 );
 
 macro_rules! impl_try_from {
-	($($kind:ident: $type:ty),*) => { $(
-		impl TryFrom<JsonValue> for $type {
-			type Error = &'static str;
+	($value:ident; $($kind:ident: $type:ty),*) => { $(
+		impl TryFrom<$value> for $type {
+			type Error = crate::Error;
 
-			fn try_from(mut value: JsonValue) -> Result<Self, Self::Error> {
+			fn try_from(mut value: $value) -> Result<Self, Self::Error> {
 				match &mut value {
-					JsonValue::$kind(val) => Ok(take(val)),
-					_ => Err(concat!("provided value is not a JSON ", stringify!($kind))),
+					$value::$kind(val) => Ok(std::mem::take(val).into()),
+					_ => Err(crate::TypeError::new(crate::JsonKind::$kind, value.kind()).into()),
 				}
 			}
 		}
 	)* }
 }
 
-impl_try_from!(Boolean: bool, String: String, List: Vec<JsonValue>, Object: HashMap<String, JsonValue>);
+pub(crate) use impl_try_from;
+
+impl_try_from!(JsonValue; Boolean: bool, String: String, List: Vec<JsonValue>, Object: HashMap<String, JsonValue>);
 
 macro_rules! impl_try_from_ref {
-	($($in:ty: $kind:ident => $out:ty),*) => { $(
+	($value:ident; $($in:ty: $kind:ident => $out:ty),*) => { $(
 		impl<'a> TryFrom<$in> for $out {
-			type Error = &'static str;
+			type Error = crate::Error;
 
 			fn try_from(value: $in) -> Result<Self, Self::Error> {
 				match value {
-					JsonValue::$kind(val) => Ok(val.into()),
-					_ => Err(concat!("provided value is not a JSON ", stringify!($kind))),
+					$value::$kind(val) => Ok(val.into()),
+					_ => Err(crate::TypeError::new(crate::JsonKind::$kind, value.kind()).into()),
 				}
 			}
 		}
 	)* }
 }
 
+pub(crate) use impl_try_from_ref;
+
 impl_try_from_ref!(
+	JsonValue;
 	JsonValue: Number => f64,
 	&'a JsonValue: Number => f64,
 	&'a mut JsonValue: Number => f64,
@@ -170,8 +395,8 @@ impl_try_from_ref!(
 	&'a JsonValue: Boolean => &'a bool,
 	&'a mut JsonValue: Boolean => &'a mut bool,
 
-	&'a JsonValue: String => &'a String,
-	&'a mut JsonValue: String => &'a mut String,
+	&'a JsonValue: String => &'a str,
+	&'a mut JsonValue: String => &'a mut JsonString,
 
 	&'a JsonValue: List => &'a Vec<JsonValue>,
 	&'a mut JsonValue: List => &'a mut Vec<JsonValue>,
@@ -180,182 +405,827 @@ impl_try_from_ref!(
 	&'a mut JsonValue: Object => &'a mut HashMap<String, JsonValue>
 );
 
-impl Index<usize> for JsonValue {
-	type Output = JsonValue;
+impl_tree!(JsonValue);
+
+mod private {
+	pub enum Key<'k> {
+		Index(usize),
+		Name(&'k str),
+	}
+
+	pub trait Sealed {
+		fn key(&self) -> Key<'_>;
+	}
+
+	impl Sealed for usize {
+		fn key(&self) -> Key<'_> {
+			Key::Index(*self)
+		}
+	}
+
+	impl Sealed for str {
+		fn key(&self) -> Key<'_> {
+			Key::Name(self)
+		}
+	}
+
+	impl Sealed for String {
+		fn key(&self) -> Key<'_> {
+			Key::Name(self)
+		}
+	}
+
+	impl<T: ?Sized + Sealed> Sealed for &T {
+		fn key(&self) -> Key<'_> {
+			(**self).key()
+		}
+	}
+}
+
+use private::Key;
+
+// The other value types look up their children with index.key() instead of going through here
+pub trait JsonIndex: private::Sealed {
+	fn index_into<'v>(&self, value: &'v JsonValue) -> Option<&'v JsonValue> {
+		get_in(value, self.key())
+	}
+
+	fn index_into_mut<'v>(&self, value: &'v mut JsonValue) -> Option<&'v mut JsonValue> {
+		get_in_mut(value, self.key())
+	}
+}
+
+impl JsonIndex for usize {}
+impl JsonIndex for str {}
+impl JsonIndex for String {}
+impl<T: ?Sized + JsonIndex> JsonIndex for &T {}
+
+impl JsonValue {
+	// Like at followed by try_into, except that a TypeError carries the index as its path. A
+	// missing value converts like null.
+	pub fn get_as<'v, T>(&'v self, index: impl JsonIndex) -> Result<T, Error>
+	where
+		T: TryFrom<&'v JsonValue, Error = Error>,
+	{
+		T::try_from(self.at(&index)).map_err(|err| match err {
+			Error::Type(err) => {
+				let pointer = match index.key() {
+					Key::Index(idx) => JsonPointer::root().index(idx),
+					Key::Name(key) => JsonPointer::root().key(key),
+				};
+				err.with_path(pointer.to_string()).into()
+			}
+			err => err,
+		})
+	}
+
+	pub fn as_list(&self) -> Option<&Vec<JsonValue>> {
+		self.try_into().ok()
+	}
 
-	fn index(&self, idx: usize) -> &Self::Output {
-		&(<&Vec<_>>::try_from(self).unwrap())[idx]
+	pub fn as_list_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+		self.try_into().ok()
+	}
+
+	pub fn as_object(&self) -> Option<&HashMap<String, JsonValue>> {
+		self.try_into().ok()
+	}
+
+	pub fn as_object_mut(&mut self) -> Option<&mut HashMap<String, JsonValue>> {
+		self.try_into().ok()
 	}
 }
 
-impl Index<&str> for JsonValue {
-	type Output = JsonValue;
+// Fluent builders, which panic if used on the wrong kind of value just like indexing does
+impl JsonValue {
+	pub fn object() -> Self {
+		JsonValue::Object(JsonObject::new())
+	}
+
+	pub fn list() -> Self {
+		JsonValue::List(Vec::new())
+	}
+
+	#[must_use]
+	pub fn with(mut self, key: impl Into<String>, value: impl Into<JsonValue>) -> Self {
+		let inner: &mut HashMap<_, _> = (&mut self).try_into().unwrap();
+		inner.insert(key.into(), value.into());
+		self
+	}
+
+	#[must_use]
+	pub fn with_opt(self, key: impl Into<String>, value: Option<impl Into<JsonValue>>) -> Self {
+		match value {
+			Some(value) => self.with(key, value),
+			None => self,
+		}
+	}
+
+	#[must_use]
+	pub fn push(mut self, value: impl Into<JsonValue>) -> Self {
+		let inner: &mut Vec<_> = (&mut self).try_into().unwrap();
+		inner.push(value.into());
+		self
+	}
 
-	fn index(&self, key: &str) -> &Self::Output {
-		&(<&HashMap<_, _>>::try_from(self).unwrap())[key]
+	#[must_use]
+	pub fn extend<I: IntoIterator>(mut self, iter: I) -> Self
+	where
+		Self: Extend<I::Item>,
+	{
+		Extend::extend(&mut self, iter);
+		self
 	}
 }
 
-impl IndexMut<usize> for JsonValue {
-	fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
-		&mut (<&mut Vec<_>>::try_from(self).unwrap())[idx]
+impl<V: Into<JsonValue>> Extend<V> for JsonValue {
+	fn extend<I: IntoIterator<Item = V>>(&mut self, iter: I) {
+		let inner: &mut Vec<_> = self.try_into().unwrap();
+		inner.extend(iter.into_iter().map(Into::into));
 	}
 }
 
-impl IndexMut<&str> for JsonValue {
-	fn index_mut(&mut self, key: &str) -> &mut Self::Output {
+impl<K: Into<String>, V: Into<JsonValue>> Extend<(K, V)> for JsonValue {
+	fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
 		let inner: &mut HashMap<_, _> = self.try_into().unwrap();
-		inner.entry(key.into()).or_insert(JsonValue::Null)
+		inner.extend(iter.into_iter().map(|(key, val)| (key.into(), val.into())));
 	}
 }
 
-impl JsonValue {
-	fn write_escaped(f: &mut Formatter, s: &str) -> Result<(), fmt::Error> {
-		f.write_char('"')?;
-		for c in s.chars() {
-			match c {
-				'"' => f.write_str("\\\"")?,
-				'\n' => f.write_str("\\n")?,
-				'\r' => f.write_str("\\r")?,
-				'\t' => f.write_str("\\t")?,
-				'\\' => f.write_str("\\\\")?,
-				c if c == 8 as char => f.write_str("\\b")?,
-				c if c == 12 as char => f.write_str("\\f")?,
-				c if c.is_ascii_control() => write!(f, "\\u{:0>4x}", c as u32)?,
-				c => f.write_char(c)?,
-			}
+impl<V: Into<JsonValue>> FromIterator<V> for JsonValue {
+	fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+		JsonValue::list().extend(iter)
+	}
+}
+
+impl<K: Into<String>, V: Into<JsonValue>> FromIterator<(K, V)> for JsonValue {
+	fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+		JsonValue::object().extend(iter)
+	}
+}
+
+pub(crate) enum Node<'v, T: Tree + 'v> {
+	Null,
+	Boolean(bool),
+	Number(f64),
+	String(&'v str),
+	List(T::ListIter<'v>),
+	Object(T::ObjectIter<'v>),
+}
+
+pub(crate) trait Tree: Sized {
+	type ListIter<'v>: ExactSizeIterator<Item = &'v Self>
+	where
+		Self: 'v;
+	type ObjectIter<'v>: ExactSizeIterator<Item = (&'v str, &'v Self)>
+	where
+		Self: 'v;
+
+	fn node(&self) -> Node<'_, Self>;
+	fn items(&self) -> Option<&[Self]>;
+	fn items_mut(&mut self) -> Option<&mut [Self]>;
+	fn lookup(&self, key: &str) -> Option<&Self>;
+	fn lookup_mut(&mut self, key: &str) -> Option<&mut Self>;
+	// Like lookup_mut, but inserts null if the key doesn't exist
+	fn entry(&mut self, key: &str) -> Option<&mut Self>;
+	// Moves out the children of a container, so that dropping them doesn't recurse
+	fn take_children(&mut self, out: &mut Vec<Self>);
+
+	fn kind(&self) -> JsonKind {
+		self.node().kind()
+	}
+
+	fn entries(&self) -> usize {
+		match self.node() {
+			Node::List(iter) => iter.len(),
+			Node::Object(iter) => iter.len(),
+			_ => 0,
+		}
+	}
+}
+
+impl<T: Tree> Node<'_, T> {
+	pub(crate) fn kind(&self) -> JsonKind {
+		match self {
+			Node::Null => JsonKind::Null,
+			Node::Boolean(_) => JsonKind::Boolean,
+			Node::Number(_) => JsonKind::Number,
+			Node::String(_) => JsonKind::String,
+			Node::List(_) => JsonKind::List,
+			Node::Object(_) => JsonKind::Object,
 		}
-		f.write_char('"')
 	}
+}
+
+pub(crate) fn get_in<'v, T: Tree>(value: &'v T, key: Key) -> Option<&'v T> {
+	match key {
+		Key::Index(idx) => value.items()?.get(idx),
+		Key::Name(key) => value.lookup(key),
+	}
+}
+
+pub(crate) fn get_in_mut<'v, T: Tree>(value: &'v mut T, key: Key) -> Option<&'v mut T> {
+	match key {
+		Key::Index(idx) => value.items_mut()?.get_mut(idx),
+		Key::Name(key) => value.lookup_mut(key),
+	}
+}
 
-	fn maybe_newline(f: &mut Formatter, flag: bool, depth: usize) -> Result<(), fmt::Error> {
-		if !flag {
-			return Ok(());
+// Every value type panics the same way when indexed: the wrong kind of value unwraps the error
+// its TryFrom conversions return, and a missing entry panics like Vec and HashMap do
+fn expect_kind<T: Tree>(value: &T, expected: JsonKind) {
+	let result: Result<(), Error> = match value.kind() {
+		kind if kind == expected => Ok(()),
+		kind => Err(TypeError::new(expected, kind).into()),
+	};
+	result.unwrap()
+}
+
+pub(crate) fn index<'v, T: Tree>(value: &'v T, key: Key) -> &'v T {
+	match key {
+		Key::Index(idx) => {
+			expect_kind(value, JsonKind::List);
+			&value.items().unwrap()[idx]
+		}
+		Key::Name(key) => {
+			expect_kind(value, JsonKind::Object);
+			value.lookup(key).expect("no entry found for key")
 		}
-		f.write_char('\n')?;
-		repeat_with(|| f.write_char('\t')).take(depth).collect()
 	}
+}
 
-	fn serialize(&self, f: &mut Formatter, pretty: bool) -> Result<(), fmt::Error> {
-		enum StackItem<'a> {
-			TopLevel,
-			List(std::slice::Iter<'a, JsonValue>),
-			Object(std::collections::hash_map::Iter<'a, String, JsonValue>),
+pub(crate) fn index_mut<'v, T: Tree>(value: &'v mut T, key: Key) -> &'v mut T {
+	match key {
+		Key::Index(idx) => {
+			expect_kind(value, JsonKind::List);
+			&mut value.items_mut().unwrap()[idx]
+		}
+		Key::Name(key) => {
+			expect_kind(value, JsonKind::Object);
+			value.entry(key).unwrap()
 		}
+	}
+}
 
-		let mut stack = vec![StackItem::TopLevel];
-		let mut write_comma = false;
-		let mut write_nl_before_val = false;
-		let mut write_nl_after_val = true;
+pub(crate) fn drop_tree<T: Tree>(value: &mut T) {
+	let mut stack = vec![];
+	value.take_children(&mut stack);
+	while let Some(mut last) = stack.pop() {
+		last.take_children(&mut stack);
+	}
+}
 
-		loop {
-			let next = match stack.last_mut() {
-				Some(StackItem::TopLevel) => stack.pop().map(|_| self).unwrap(),
-				Some(StackItem::List(iter)) => {
-					let Some(val) = iter.next() else {
-						stack.pop();
-						JsonValue::maybe_newline(f, write_nl_after_val && pretty, stack.len())?;
-						f.write_char(']')?;
-						write_nl_after_val = true;
-						write_comma = true;
-						continue;
-					};
+// Kind predicates for any value type with a kind method
+macro_rules! impl_predicates {
+	($type:ident $(<$lt:lifetime>)?) => {
+		impl$(<$lt>)? $type$(<$lt>)? {
+				pub fn is_null(&self) -> bool {
+					self.kind() == crate::JsonKind::Null
+				}
 
-					val
+				pub fn is_bool(&self) -> bool {
+					self.kind() == crate::JsonKind::Boolean
 				}
-				Some(StackItem::Object(iter)) => {
-					let Some((key, val)) = iter.next() else {
-						stack.pop();
-						JsonValue::maybe_newline(f, write_nl_after_val && pretty, stack.len())?;
-						f.write_char('}')?;
-						write_nl_after_val = true;
-						write_comma = true;
-						continue;
-					};
 
-					if write_comma {
-						f.write_char(',')?;
-						write_comma = false;
-					}
+				pub fn is_number(&self) -> bool {
+					self.kind() == crate::JsonKind::Number
+				}
 
-					JsonValue::maybe_newline(f, pretty, stack.len())?;
-					JsonValue::write_escaped(f, key)?;
-					f.write_char(':')?;
-					write_nl_before_val = false;
+				pub fn is_string(&self) -> bool {
+					self.kind() == crate::JsonKind::String
+				}
 
-					if pretty {
-						f.write_char(' ')?;
-					}
+				pub fn is_list(&self) -> bool {
+					self.kind() == crate::JsonKind::List
+				}
+
+				pub fn is_object(&self) -> bool {
+					self.kind() == crate::JsonKind::Object
+				}
+		}
+	};
+}
+
+pub(crate) use impl_predicates;
+
+// The accessors, indexing and iterative drop that every owned value type shares, all built on
+// its Tree impl
+macro_rules! impl_tree {
+	($type:ident) => {
+		crate::impl_predicates!($type);
+
+		impl $type {
+			pub fn kind(&self) -> crate::JsonKind {
+				crate::Tree::kind(self)
+			}
+
+			pub fn get(&self, index: impl crate::JsonIndex) -> Option<&$type> {
+				crate::get_in(self, index.key())
+			}
+
+			pub fn get_mut(&mut self, index: impl crate::JsonIndex) -> Option<&mut $type> {
+				crate::get_in_mut(self, index.key())
+			}
+
+			// Like indexing, but returns null instead of panicking
+			pub fn at(&self, index: impl crate::JsonIndex) -> &$type {
+				static NULL: $type = $type::Null;
+				self.get(index).unwrap_or(&NULL)
+			}
+
+			pub fn as_bool(&self) -> Option<bool> {
+				match crate::Tree::node(self) {
+					crate::Node::Boolean(b) => Some(b),
+					_ => None,
+				}
+			}
+
+			pub fn as_f64(&self) -> Option<f64> {
+				match crate::Tree::node(self) {
+					crate::Node::Number(num) => Some(num),
+					_ => None,
+				}
+			}
 
-					val
+			pub fn as_str(&self) -> Option<&str> {
+				match crate::Tree::node(self) {
+					crate::Node::String(s) => Some(s),
+					_ => None,
 				}
-				None => return Ok(()),
+			}
+		}
+
+		impl Drop for $type {
+			fn drop(&mut self) {
+				crate::drop_tree(self)
+			}
+		}
+
+		impl std::ops::Index<usize> for $type {
+			type Output = $type;
+
+			fn index(&self, idx: usize) -> &Self::Output {
+				crate::index(self, crate::Key::Index(idx))
+			}
+		}
+
+		impl std::ops::Index<&str> for $type {
+			type Output = $type;
+
+			fn index(&self, key: &str) -> &Self::Output {
+				crate::index(self, crate::Key::Name(key))
+			}
+		}
+
+		impl std::ops::IndexMut<usize> for $type {
+			fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+				crate::index_mut(self, crate::Key::Index(idx))
+			}
+		}
+
+		// Inserts null if the key doesn't exist
+		impl std::ops::IndexMut<&str> for $type {
+			fn index_mut(&mut self, key: &str) -> &mut Self::Output {
+				crate::index_mut(self, crate::Key::Name(key))
+			}
+		}
+	};
+}
+
+pub(crate) use impl_tree;
+
+pub(crate) trait Builder<'a>: Tree {
+	// Whatever the keys of one document share, like the KeyInterner behind InternedValue
+	type Keys: Default;
+
+	fn null() -> Self;
+	fn boolean(val: bool) -> Self;
+	fn number(val: f64) -> Self;
+	fn string(val: Cow<'a, str>) -> Self;
+	fn list() -> Self;
+	fn object() -> Self;
+	fn push_value(&mut self, val: Self);
+	fn insert_value(&mut self, keys: &mut Self::Keys, key: Cow<'a, str>, val: Self);
+}
+
+impl Tree for JsonValue {
+	type ListIter<'v> = std::slice::Iter<'v, JsonValue>;
+	type ObjectIter<'v> = std::iter::Map<
+		std::collections::hash_map::Iter<'v, String, JsonValue>,
+		fn((&'v String, &'v JsonValue)) -> (&'v str, &'v JsonValue),
+	>;
+
+	fn node(&self) -> Node<'_, Self> {
+		match self {
+			JsonValue::Null => Node::Null,
+			JsonValue::Boolean(b) => Node::Boolean(*b),
+			JsonValue::Number(num) => Node::Number(num.into()),
+			JsonValue::String(s) => Node::String(s),
+			JsonValue::List(ls) => Node::List(ls.iter()),
+			JsonValue::Object(obj) => Node::Object(obj.iter().map(str_key as _)),
+		}
+	}
+
+	fn items(&self) -> Option<&[Self]> {
+		match self {
+			JsonValue::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn items_mut(&mut self) -> Option<&mut [Self]> {
+		match self {
+			JsonValue::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	fn lookup(&self, key: &str) -> Option<&Self> {
+		match self {
+			JsonValue::Object(obj) => obj.get(key),
+			_ => None,
+		}
+	}
+
+	// Doesn't create the map of an empty object just to find nothing in it
+	fn lookup_mut(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			JsonValue::Object(obj) => obj.0.as_mut()?.get_mut(key),
+			_ => None,
+		}
+	}
+
+	fn entry(&mut self, key: &str) -> Option<&mut Self> {
+		match self {
+			JsonValue::Object(obj) => Some(obj.entry(key.into()).or_insert(JsonValue::Null)),
+			_ => None,
+		}
+	}
+
+	fn take_children(&mut self, out: &mut Vec<Self>) {
+		match self {
+			JsonValue::List(ls) => out.append(ls),
+			JsonValue::Object(obj) => {
+				out.extend(take(obj).0.into_iter().flat_map(|obj| obj.into_values()))
+			}
+			_ => {}
+		}
+	}
+}
+
+impl<'a> Builder<'a> for JsonValue {
+	type Keys = ();
+
+	fn null() -> Self {
+		JsonValue::Null
+	}
+
+	fn boolean(val: bool) -> Self {
+		JsonValue::Boolean(val)
+	}
+
+	fn number(val: f64) -> Self {
+		JsonValue::from(val)
+	}
+
+	fn string(val: Cow<'a, str>) -> Self {
+		JsonValue::String(val.into())
+	}
+
+	fn list() -> Self {
+		JsonValue::List(Vec::new())
+	}
+
+	fn object() -> Self {
+		JsonValue::Object(JsonObject::new())
+	}
+
+	fn push_value(&mut self, val: Self) {
+		if let JsonValue::List(ls) = self {
+			ls.push(val);
+		}
+	}
+
+	fn insert_value(&mut self, _: &mut (), key: Cow<'a, str>, val: Self) {
+		if let JsonValue::Object(obj) = self {
+			obj.insert(key.into_owned(), val);
+		}
+	}
+}
+
+pub(crate) fn str_key<'v, K: AsRef<str>, V>((key, val): (&'v K, &'v V)) -> (&'v str, &'v V) {
+	(key.as_ref(), val)
+}
+
+pub(crate) fn tree_eq<T: Tree>(lhs: &T, rhs: &T) -> bool {
+	let mut lhs_stack = vec![lhs];
+	let mut rhs_stack = vec![rhs];
+
+	while let (Some(lhs), Some(rhs)) = (lhs_stack.pop(), rhs_stack.pop()) {
+		match (lhs.node(), rhs.node()) {
+			(Node::List(l), Node::List(r)) if l.len() == r.len() => {
+				lhs_stack.extend(l);
+				rhs_stack.extend(r);
+			}
+			(Node::Object(l), Node::Object(r)) if l.len() == r.len() => {
+				for (key, val1) in l {
+					let Some(val2) = rhs.lookup(key) else {
+						return false;
+					};
+					lhs_stack.push(val1);
+					rhs_stack.push(val2);
+				}
+			}
+			(Node::Boolean(l), Node::Boolean(r)) if l == r => {}
+			(Node::Number(l), Node::Number(r)) if l == r => {}
+			(Node::String(l), Node::String(r)) if l == r => {}
+			(Node::Null, Node::Null) => {}
+			_ => return false,
+		}
+	}
+	true
+}
+
+pub(crate) fn build_from<'v, S: Tree, T: Builder<'v>>(source: &'v S) -> T {
+	enum Children<'v, S: Tree + 'v> {
+		List(S::ListIter<'v>),
+		Object(S::ObjectIter<'v>, &'v str),
+	}
+
+	let mut stack: Vec<(T, Children<S>)> = vec![];
+	let mut keys = T::Keys::default();
+	let mut next = source;
+
+	loop {
+		let mut value = match next.node() {
+			Node::Null => Some(T::null()),
+			Node::Boolean(b) => Some(T::boolean(b)),
+			Node::Number(num) => Some(T::number(num)),
+			Node::String(s) => Some(T::string(Cow::Borrowed(s))),
+			Node::List(iter) => {
+				stack.push((T::list(), Children::List(iter)));
+				None
+			}
+			Node::Object(iter) => {
+				stack.push((T::object(), Children::Object(iter, "")));
+				None
+			}
+		};
+
+		loop {
+			let Some((parent, children)) = stack.last_mut() else {
+				return value.unwrap();
 			};
 
-			if write_comma {
-				f.write_char(',')?;
+			match (children, value.take()) {
+				(Children::List(_), Some(val)) => parent.push_value(val),
+				(Children::Object(_, key), Some(val)) => {
+					parent.insert_value(&mut keys, Cow::Borrowed(key), val)
+				}
+				_ => {}
 			}
 
-			JsonValue::maybe_newline(f, write_nl_before_val && pretty, stack.len())?;
-			write_nl_before_val = true;
-			write_nl_after_val = true;
-			write_comma = true;
+			match stack.last_mut().unwrap() {
+				(_, Children::List(iter)) => {
+					if let Some(child) = iter.next() {
+						next = child;
+						break;
+					}
+				}
+				(_, Children::Object(iter, key)) => {
+					if let Some((child_key, child)) = iter.next() {
+						*key = child_key;
+						next = child;
+						break;
+					}
+				}
+			}
+			value = stack.pop().map(|(parent, _)| parent);
+		}
+	}
+}
+
+fn write_escaped(f: &mut Formatter, s: &str) -> Result<(), fmt::Error> {
+	f.write_char('"')?;
+	for c in s.chars() {
+		match c {
+			'"' => f.write_str("\\\"")?,
+			'\n' => f.write_str("\\n")?,
+			'\r' => f.write_str("\\r")?,
+			'\t' => f.write_str("\\t")?,
+			'\\' => f.write_str("\\\\")?,
+			c if c == 8 as char => f.write_str("\\b")?,
+			c if c == 12 as char => f.write_str("\\f")?,
+			c if c.is_ascii_control() => write!(f, "\\u{:0>4x}", c as u32)?,
+			c => f.write_char(c)?,
+		}
+	}
+	f.write_char('"')
+}
+
+fn maybe_newline(f: &mut Formatter, flag: bool, depth: usize) -> Result<(), fmt::Error> {
+	if !flag {
+		return Ok(());
+	}
+	f.write_char('\n')?;
+	repeat_with(|| f.write_char('\t')).take(depth).collect()
+}
+
+fn serialize<T: Tree>(value: &T, f: &mut Formatter, pretty: bool) -> Result<(), fmt::Error> {
+	enum StackItem<'v, T: Tree + 'v> {
+		TopLevel,
+		List(T::ListIter<'v>),
+		Object(T::ObjectIter<'v>),
+	}
 
-			match next {
-				JsonValue::List(ls) => {
-					f.write_char('[')?;
-					stack.push(StackItem::List(ls.iter()));
+	let mut stack = vec![StackItem::<T>::TopLevel];
+	let mut write_comma = false;
+	let mut write_nl_before_val = false;
+	let mut write_nl_after_val = true;
+
+	loop {
+		let next = match stack.last_mut() {
+			Some(StackItem::TopLevel) => stack.pop().map(|_| value).unwrap(),
+			Some(StackItem::List(iter)) => {
+				let Some(val) = iter.next() else {
+					stack.pop();
+					maybe_newline(f, write_nl_after_val && pretty, stack.len())?;
+					f.write_char(']')?;
+					write_nl_after_val = true;
+					write_comma = true;
+					continue;
+				};
+
+				val
+			}
+			Some(StackItem::Object(iter)) => {
+				let Some((key, val)) = iter.next() else {
+					stack.pop();
+					maybe_newline(f, write_nl_after_val && pretty, stack.len())?;
+					f.write_char('}')?;
+					write_nl_after_val = true;
+					write_comma = true;
+					continue;
+				};
+
+				if write_comma {
+					f.write_char(',')?;
 					write_comma = false;
-					write_nl_after_val = !ls.is_empty();
 				}
-				JsonValue::Object(obj) => {
-					f.write_char('{')?;
-					stack.push(StackItem::Object(obj.iter()));
-					write_comma = false;
-					write_nl_after_val = !obj.is_empty();
+
+				maybe_newline(f, pretty, stack.len())?;
+				write_escaped(f, key)?;
+				f.write_char(':')?;
+				write_nl_before_val = false;
+
+				if pretty {
+					f.write_char(' ')?;
 				}
-				JsonValue::Null => f.write_str("null")?,
-				JsonValue::Number(num) => write!(f, "{}", f64::from(num))?,
-				JsonValue::Boolean(b) => write!(f, "{b}")?,
-				JsonValue::String(s) => JsonValue::write_escaped(f, s)?,
-			};
+
+				val
+			}
+			None => return Ok(()),
+		};
+
+		if write_comma {
+			f.write_char(',')?;
 		}
+
+		maybe_newline(f, write_nl_before_val && pretty, stack.len())?;
+		write_nl_before_val = true;
+		write_nl_after_val = true;
+		write_comma = true;
+
+		match next.node() {
+			Node::List(ls) => {
+				f.write_char('[')?;
+				write_comma = false;
+				write_nl_after_val = ls.len() > 0;
+				stack.push(StackItem::List(ls));
+			}
+			Node::Object(obj) => {
+				f.write_char('{')?;
+				write_comma = false;
+				write_nl_after_val = obj.len() > 0;
+				stack.push(StackItem::Object(obj));
+			}
+			Node::Null => f.write_str("null")?,
+			Node::Number(num) => write!(f, "{num}")?,
+			Node::Boolean(b) => write!(f, "{b}")?,
+			Node::String(s) => write_escaped(f, s)?,
+		};
 	}
 }
 
 impl Display for JsonValue {
 	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-		self.serialize(f, false)
+		serialize(self, f, false)
 	}
 }
 
 impl Debug for JsonValue {
 	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
-		self.serialize(f, true)
+		serialize(self, f, true)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+	pub max_depth: usize,
+	pub max_bytes: usize,
+	pub max_string_len: usize,
+	pub max_entries: usize,
+	pub max_number_len: usize,
+}
+
+impl Default for ParseLimits {
+	fn default() -> Self {
+		ParseLimits {
+			max_depth: usize::MAX,
+			max_bytes: usize::MAX,
+			max_string_len: usize::MAX,
+			max_entries: usize::MAX,
+			max_number_len: usize::MAX,
+		}
 	}
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+	Error,
+	FirstWins,
+	#[default]
+	LastWins,
+	CollectIntoList,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+	pub limits: ParseLimits,
+	pub duplicate_keys: DuplicateKeys,
+}
+
 impl FromStr for JsonValue {
-	type Err = String;
+	type Err = Error;
 
 	fn from_str(input: &str) -> Result<Self, Self::Err> {
-		#[derive(Clone, Copy, PartialEq)]
-		enum Expecting {
-			CommaOrBrace,
-			CommaOrBracket,
-			Key,
-			KeyOrBrace,
-			Value,
-			ValueOrBracket,
-		}
-		use Expecting::*;
-
-		let bytes = input.as_bytes();
-		let mut stack = vec![];
-		let mut key_stack = vec![];
-		let mut i = 0;
-		let mut expect = Value;
+		JsonValue::parse_with(input, &ParseOptions::default())
+	}
+}
+
+impl JsonValue {
+	pub fn parse_with(input: &str, options: &ParseOptions) -> Result<JsonValue, Error> {
+		parse_all(input, options).map_err(Error::from)
+	}
+}
 
+pub(crate) fn parse_all<'a, T: Builder<'a>>(
+	input: &'a str,
+	options: &ParseOptions,
+) -> Result<T, ParseError> {
+	let max_bytes = options.limits.max_bytes;
+	if input.len() > max_bytes {
+		return Err(ParseError {
+			kind: Limit::Bytes.into(),
+			offset: max_bytes,
+		});
+	}
+
+	let (value, end) = parse_prefix(input, options)?;
+	match input[end..].chars().next() {
+		Some(c) => Err(ParseError {
+			kind: format!("unexpected character: {c}").into(),
+			offset: end,
+		}),
+		None => Ok(value),
+	}
+}
+
+pub(crate) fn parse_prefix<'a, T: Builder<'a>>(
+	input: &'a str,
+	options: &ParseOptions,
+) -> Result<(T, usize), ParseError> {
+	#[derive(Clone, Copy, PartialEq)]
+	enum Expecting {
+		CommaOrBrace,
+		CommaOrBracket,
+		Key,
+		KeyOrBrace,
+		Value,
+		ValueOrBracket,
+	}
+	use Expecting::*;
+
+	let limits = &options.limits;
+	let bytes = input.as_bytes();
+	let mut stack = vec![];
+	let mut key_stack = vec![];
+	let mut keys = T::Keys::default();
+	let mut collected_keys = vec![];
+	let mut expect = Value;
+
+	let mut i = 0;
+	let result = (|| -> Result<(T, usize), ParseErrorKind> {
 		loop {
 			let next = match (bytes.get(i).ok_or("unexpected end of input")?, expect) {
 				(b' ' | b'\t' | b'\n' | b'\r', _) if !stack.is_empty() => {
@@ -365,11 +1235,16 @@ impl FromStr for JsonValue {
 				(b'{', Value | ValueOrBracket) => {
 					i += 1;
 					expect = KeyOrBrace;
-					stack.push(HashMap::new().into());
+					if stack.len() >= limits.max_depth {
+						Err(Limit::Depth)?
+					}
+					stack.push(T::object());
+					collected_keys.push(HashSet::new());
 					continue;
 				}
 				(b'}', CommaOrBrace | KeyOrBrace) => {
 					i += 1;
+					collected_keys.pop();
 					stack.pop().ok_or("unexpected closing brace")?
 				}
 				(b',', CommaOrBracket | CommaOrBrace) => {
@@ -380,7 +1255,10 @@ impl FromStr for JsonValue {
 				(b'[', Value | ValueOrBracket) => {
 					i += 1;
 					expect = ValueOrBracket;
-					stack.push(Vec::new().into());
+					if stack.len() >= limits.max_depth {
+						Err(Limit::Depth)?
+					}
+					stack.push(T::list());
 					continue;
 				}
 				(b']', CommaOrBracket | ValueOrBracket) => {
@@ -388,59 +1266,8 @@ impl FromStr for JsonValue {
 					stack.pop().ok_or("unexpected closing bracket")?
 				}
 				(b'"', Value | ValueOrBracket | Key | KeyOrBrace) => {
-					i += 1;
-					let mut s = String::new();
-
-					loop {
-						let end = i + bytes
-							.get(i..)
-							.unwrap_or_default()
-							.iter()
-							.position(|&c| c == b'"' || c == b'\\' || c.is_ascii_control())
-							.ok_or("missing end quote")?;
-
-						s.push_str(&input[i..end]);
-						i = end;
-
-						s.push(match (bytes[i], bytes.get(i + 1)) {
-							(b'"', _) => break,
-							(b'\\', Some(b'"')) => '"',
-							(b'\\', Some(b'\\')) => '\\',
-							(b'\\', Some(b'/')) => '/',
-							(b'\\', Some(b'b')) => 8 as char,
-							(b'\\', Some(b'f')) => 12 as char,
-							(b'\\', Some(b'n')) => '\n',
-							(b'\\', Some(b'r')) => '\r',
-							(b'\\', Some(b't')) => '\t',
-							(b'\\', Some(b'u')) => {
-								let mut codepoint = input
-									.get(i + 2..i + 6)
-									.and_then(|s| u32::from_str_radix(s, 16).ok())
-									.ok_or("invalid hex string")?;
-								i += 4;
-
-								let is_surrogate = matches!(codepoint, 0xd800..0xdc00);
-								if is_surrogate && matches!(bytes.get(i + 2..i + 4), Some(b"\\u")) {
-									codepoint = input
-										.get(i + 4..i + 8)
-										.and_then(|s| u32::from_str_radix(s, 16).ok())
-										.ok_or("invalid hex string")?
-										.checked_sub(0xdc00)
-										.filter(|&num| num < 0xe000 - 0xdc00)
-										.map(|num| 0x10000 + num + (codepoint - 0xd800) * 1024)
-										.inspect(|_| i += 6)
-										.unwrap_or(codepoint);
-								}
-								char::from_u32(codepoint).unwrap_or('�')
-							}
-							(b'\\', Some(c)) => Err(format!("invalid escape sequence: {c}"))?,
-							(b'\\', None) => Err("missing escape sequence")?,
-							(c, _) => Err(format!("illegal control character: 0x{c:x}"))?,
-						});
-
-						i += 2;
-					}
-					i += 1;
+					let start = i;
+					let s = parse_string(input, &mut i, limits)?;
 
 					if matches!(expect, Key | KeyOrBrace) {
 						i += bytes
@@ -450,86 +1277,201 @@ impl FromStr for JsonValue {
 							.position(|&c| !matches!(c, b' ' | b'\t' | b'\n' | b'\r'))
 							.and_then(|pos| (bytes[i + pos] == b':').then_some(pos + 1))
 							.ok_or("missing colon")?;
-						key_stack.push(s);
+						key_stack.push((s, start));
 						expect = Value;
 						continue;
 					}
 
-					JsonValue::from(s)
+					T::string(s)
 				}
 				(b'-' | b'0'..=b'9', Value | ValueOrBracket) => {
-					let mut decimal_places = 0;
-					let is_negative = bytes[i] == b'-';
-					i += if is_negative { 1 } else { 0 };
+					let start = i;
+					i += (bytes[i] == b'-') as usize;
 
-					let mut num = match (bytes.get(i), bytes.get(i + 1)) {
+					match (bytes.get(i), bytes.get(i + 1)) {
 						(Some(b'0'), Some(b'0'..=b'9')) => Err("illegal leading zero")?,
-						(Some(c @ b'0'..=b'9'), _) => (c - b'0') as f64,
-						(Some(c), _) => Err(format!("unexpected character: {}", *c as char))?,
+						(Some(b'0'..=b'9'), _) => {}
+						(Some(_), _) => Err(unexpected_char(input, i))?,
 						(None, _) => Err("unexpected end of input")?,
 					};
 
+					let mut seen_dot = false;
 					loop {
 						i += 1;
 						match bytes.get(i) {
-							Some(c @ b'0'..=b'9') if decimal_places > 0 => {
-								num += (c - b'0') as f64 / 10_f64.powi(decimal_places);
-								decimal_places += 1;
-							}
-							Some(c @ b'0'..=b'9') => num = num * 10. + (c - b'0') as f64,
-							Some(b'.') if decimal_places == 0 => decimal_places = 1,
+							Some(b'0'..=b'9') => {}
+							Some(b'.') if !seen_dot => seen_dot = true,
 							Some(b'e' | b'E') => {
 								i += 1;
-
-								let mut exp = match bytes.get(i).ok_or("unexpected end of input")? {
-									c @ b'0'..=b'9' => (c - b'0') as f64,
-									b'-' | b'+' => 0.,
-									c => Err(format!("unexpected character: {}", *c as char))?,
-								};
-								let exp_is_negative = bytes[i] == b'-';
+								match bytes.get(i).ok_or("unexpected end of input")? {
+									b'0'..=b'9' | b'-' | b'+' => {}
+									_ => Err(unexpected_char(input, i))?,
+								}
 
 								i += 1;
-								while let Some(c @ b'0'..=b'9') = bytes.get(i) {
-									exp = exp * 10. + (c - b'0') as f64;
+								while let Some(b'0'..=b'9') = bytes.get(i) {
 									i += 1;
 								}
-
-								num *= 10_f64.powf(if exp_is_negative { -exp } else { exp });
 								break;
 							}
 							_ => break,
 						};
 					}
 
-					JsonValue::from(if is_negative { -num } else { num })
+					if i - start > limits.max_number_len {
+						Err(Limit::NumberLength)?
+					}
+					// Leave the rounding to the standard library. The only forms it doesn't accept
+					// are exponents without digits, which count as zero.
+					let num = input[start..i].trim_end_matches(['e', 'E', '+', '-']);
+					T::number(num.parse().map_err(|_| "invalid number")?)
 				}
 				(b't', Value | ValueOrBracket) if bytes.get(i..i + 4) == Some(b"true") => {
 					i += 4;
-					JsonValue::from(true)
+					T::boolean(true)
 				}
 				(b'f', Value | ValueOrBracket) if bytes.get(i..i + 5) == Some(b"false") => {
 					i += 5;
-					JsonValue::from(false)
+					T::boolean(false)
 				}
 				(b'n', Value | ValueOrBracket) if bytes.get(i..i + 4) == Some(b"null") => {
 					i += 4;
-					JsonValue::Null
+					T::null()
 				}
-				(&c, _) => Err(format!("unexpected character: {}", c as char))?,
+				_ => Err(unexpected_char(input, i))?,
 			};
 
+			// Checked as the value is read rather than up front, so that a stream applies the
+			// limit to each value instead of to everything after it
+			if i > limits.max_bytes {
+				i = limits.max_bytes;
+				Err(Limit::Bytes)?
+			}
+
 			match stack.last_mut() {
-				Some(JsonValue::List(ls)) => {
-					ls.push(next);
+				Some(parent) if parent.entries() >= limits.max_entries => Err(Limit::Entries)?,
+				Some(parent) if parent.kind() != JsonKind::Object => {
+					parent.push_value(next);
 					expect = CommaOrBracket;
 				}
-				Some(JsonValue::Object(obj)) => {
-					obj.insert(key_stack.pop().unwrap(), next);
+				Some(obj) => {
+					let (key, key_start) = key_stack.pop().unwrap();
+					let collected = collected_keys.last_mut().unwrap();
+					insert_entry(obj, &mut keys, key, next, options.duplicate_keys, collected)
+						.inspect_err(|_| i = key_start)?;
 					expect = CommaOrBrace;
 				}
-				_ if i == bytes.len() => return Ok(next),
-				_ => {}
+				None => return Ok((next, i)),
 			};
 		}
+	})();
+
+	result.map_err(|kind| ParseError { kind, offset: i })
+}
+
+// Decodes the whole character at i, which may be more than one byte
+pub(crate) fn unexpected_char(input: &str, i: usize) -> ParseErrorKind {
+	let c = input.get(i..).and_then(|rest| rest.chars().next());
+	format!(
+		"unexpected character: {}",
+		c.unwrap_or(char::REPLACEMENT_CHARACTER)
+	)
+	.into()
+}
+
+// Starts at an opening quote and stops just past the closing one
+pub(crate) fn parse_string<'a>(
+	input: &'a str,
+	i: &mut usize,
+	limits: &ParseLimits,
+) -> Result<Cow<'a, str>, ParseErrorKind> {
+	let bytes = input.as_bytes();
+	*i += 1;
+	let mut s = Cow::Borrowed("");
+
+	loop {
+		let end = *i
+			+ bytes
+				.get(*i..)
+				.unwrap_or_default()
+				.iter()
+				.position(|&c| c == b'"' || c == b'\\' || c.is_ascii_control())
+				.ok_or("missing end quote")?;
+
+		if s.is_empty() {
+			s = Cow::Borrowed(&input[*i..end]);
+		} else {
+			s.to_mut().push_str(&input[*i..end]);
+		}
+		*i = end;
+		if s.len() > limits.max_string_len {
+			Err(Limit::StringLength)?
+		}
+
+		let c = match (bytes[*i], bytes.get(*i + 1)) {
+			(b'"', _) => break,
+			(b'\\', Some(b'"')) => '"',
+			(b'\\', Some(b'\\')) => '\\',
+			(b'\\', Some(b'/')) => '/',
+			(b'\\', Some(b'b')) => 8 as char,
+			(b'\\', Some(b'f')) => 12 as char,
+			(b'\\', Some(b'n')) => '\n',
+			(b'\\', Some(b'r')) => '\r',
+			(b'\\', Some(b't')) => '\t',
+			(b'\\', Some(b'u')) => {
+				let mut codepoint = input
+					.get(*i + 2..*i + 6)
+					.and_then(|s| u32::from_str_radix(s, 16).ok())
+					.ok_or("invalid hex string")?;
+				*i += 4;
+
+				let is_surrogate = matches!(codepoint, 0xd800..0xdc00);
+				if is_surrogate && matches!(bytes.get(*i + 2..*i + 4), Some(b"\\u")) {
+					codepoint = input
+						.get(*i + 4..*i + 8)
+						.and_then(|s| u32::from_str_radix(s, 16).ok())
+						.ok_or("invalid hex string")?
+						.checked_sub(0xdc00)
+						.filter(|&num| num < 0xe000 - 0xdc00)
+						.map(|num| 0x10000 + num + (codepoint - 0xd800) * 1024)
+						.inspect(|_| *i += 6)
+						.unwrap_or(codepoint);
+				}
+				char::from_u32(codepoint).unwrap_or('�')
+			}
+			(b'\\', Some(c)) => Err(format!("invalid escape sequence: {c}"))?,
+			(b'\\', None) => Err("missing escape sequence")?,
+			(c, _) => Err(format!("illegal control character: 0x{c:x}"))?,
+		};
+		s.to_mut().push(c);
+
+		*i += 2;
+	}
+	*i += 1;
+	Ok(s)
+}
+
+// Adds an entry to an object being parsed, applying the duplicate key policy
+pub(crate) fn insert_entry<'a, T: Builder<'a>>(
+	obj: &mut T,
+	keys: &mut T::Keys,
+	key: Cow<'a, str>,
+	value: T,
+	duplicate_keys: DuplicateKeys,
+	collected_keys: &mut HashSet<Cow<'a, str>>,
+) -> Result<(), ParseErrorKind> {
+	match (obj.lookup_mut(&key), duplicate_keys) {
+		(None, _) => obj.insert_value(keys, key, value),
+		(Some(_), DuplicateKeys::Error) => Err(ParseErrorKind::DuplicateKey(key.into_owned()))?,
+		(Some(_), DuplicateKeys::FirstWins) => {}
+		(Some(existing), DuplicateKeys::LastWins) => *existing = value,
+		(Some(existing), DuplicateKeys::CollectIntoList) => {
+			if collected_keys.insert(key) {
+				let first = replace(existing, T::list());
+				existing.push_value(first);
+			}
+			existing.push_value(value);
+		}
 	}
+	Ok(())
 }
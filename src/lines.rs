@@ -0,0 +1,114 @@
+use crate::{Error, JsonValue, ParseOptions};
+use std::io::{self, BufRead, Write};
+
+pub struct JsonLines<R> {
+	reader: R,
+	buf: String,
+	line: usize,
+	options: ParseOptions,
+	skip_blank_lines: bool,
+	continue_on_error: bool,
+	done: bool,
+}
+
+impl<R: BufRead> JsonLines<R> {
+	pub fn new(reader: R) -> Self {
+		JsonLines {
+			reader,
+			buf: String::new(),
+			line: 0,
+			options: ParseOptions::default(),
+			skip_blank_lines: false,
+			continue_on_error: false,
+			done: false,
+		}
+	}
+
+	pub fn options(mut self, options: ParseOptions) -> Self {
+		self.options = options;
+		self
+	}
+
+	// Blank lines are otherwise reported as parse errors
+	pub fn skip_blank_lines(mut self, skip: bool) -> Self {
+		self.skip_blank_lines = skip;
+		self
+	}
+
+	// Keep reading after a line fails to parse instead of ending the iteration
+	pub fn continue_on_error(mut self, cont: bool) -> Self {
+		self.continue_on_error = cont;
+		self
+	}
+
+	pub fn line_number(&self) -> usize {
+		self.line
+	}
+
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+}
+
+impl<R: BufRead> Iterator for JsonLines<R> {
+	type Item = Result<JsonValue, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		while !self.done {
+			self.buf.clear();
+			match self.reader.read_line(&mut self.buf) {
+				Ok(0) => self.done = true,
+				Ok(_) => {
+					self.line += 1;
+					let line = self.buf.trim_matches([' ', '\t', '\n', '\r']);
+					if line.is_empty() && self.skip_blank_lines {
+						continue;
+					}
+
+					let result =
+						JsonValue::parse_with(line, &self.options).map_err(|err| Error::Line {
+							line: self.line,
+							source: Box::new(err),
+						});
+					self.done = result.is_err() && !self.continue_on_error;
+					return Some(result);
+				}
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+				Err(err) => {
+					self.done = true;
+					return Some(Err(err.into()));
+				}
+			}
+		}
+		None
+	}
+}
+
+pub struct JsonLinesWriter<W> {
+	writer: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+	pub fn new(writer: W) -> Self {
+		JsonLinesWriter { writer }
+	}
+
+	pub fn write(&mut self, value: &JsonValue) -> Result<(), Error> {
+		Ok(writeln!(self.writer, "{value}")?)
+	}
+
+	pub fn write_all<'a>(
+		&mut self,
+		values: impl IntoIterator<Item = &'a JsonValue>,
+	) -> Result<(), Error> {
+		values.into_iter().try_for_each(|value| self.write(value))
+	}
+
+	pub fn flush(&mut self) -> Result<(), Error> {
+		Ok(self.writer.flush()?)
+	}
+
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+}
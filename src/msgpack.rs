@@ -0,0 +1,240 @@
+use crate::{Error, JsonValue, Limit, ParseError, ParseErrorKind, ParseLimits};
+
+// Fails only if a string, list or object has more than u32::MAX entries
+pub fn to_vec(value: &JsonValue) -> Result<Vec<u8>, Error> {
+	enum Item<'v> {
+		Value(&'v JsonValue),
+		Key(&'v str),
+	}
+
+	let mut out = vec![];
+	let mut stack = vec![Item::Value(value)];
+	while let Some(item) = stack.pop() {
+		let value = match item {
+			Item::Value(value) => value,
+			Item::Key(key) => {
+				write_str(&mut out, key)?;
+				continue;
+			}
+		};
+
+		match value {
+			JsonValue::Null => out.push(0xc0),
+			JsonValue::Boolean(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+			JsonValue::Number(num) => write_number(&mut out, num.into()),
+			JsonValue::String(s) => write_str(&mut out, s)?,
+			JsonValue::List(ls) => {
+				write_len(&mut out, ls.len(), 0x90, 16, 0xdc)?;
+				stack.extend(ls.iter().rev().map(Item::Value));
+			}
+			JsonValue::Object(obj) => {
+				write_len(&mut out, obj.len(), 0x80, 16, 0xde)?;
+				for (key, val) in obj {
+					stack.extend([Item::Value(val), Item::Key(key)]);
+				}
+			}
+		}
+	}
+	Ok(out)
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+	if s.len() < 32 {
+		out.push(0xa0 | s.len() as u8);
+	} else if s.len() < 0x100 {
+		out.extend([0xd9, s.len() as u8]);
+	} else {
+		write_len(out, s.len(), 0, 0, 0xda)?;
+	}
+	out.extend_from_slice(s.as_bytes());
+	Ok(())
+}
+
+// The fix variant if len is below fix_limit, then the 16-bit and 32-bit markers that follow
+fn write_len(
+	out: &mut Vec<u8>,
+	len: usize,
+	fix_marker: u8,
+	fix_limit: usize,
+	marker: u8,
+) -> Result<(), Error> {
+	if len < fix_limit {
+		out.push(fix_marker | len as u8);
+	} else if let Ok(len) = u16::try_from(len) {
+		out.push(marker);
+		out.extend(len.to_be_bytes());
+	} else {
+		let len = u32::try_from(len)
+			.map_err(|_| Error::Encode(format!("length {len} is too large for MessagePack")))?;
+		out.push(marker + 1);
+		out.extend(len.to_be_bytes());
+	}
+	Ok(())
+}
+
+// The smallest integer encoding if the number is an integer, otherwise the narrowest exact float
+fn write_number(out: &mut Vec<u8>, num: f64) {
+	const LIMIT: f64 = 18446744073709551616.;
+	let is_negative_zero = num == 0. && num.is_sign_negative();
+	if num.fract() == 0. && !is_negative_zero && (-LIMIT / 2. ..LIMIT).contains(&num) {
+		let int = num as i128;
+		match int {
+			-0x20..0x80 => out.push(int as u8),
+			0x80..0x100 => out.extend([0xcc, int as u8]),
+			0x100..0x10000 => {
+				out.push(0xcd);
+				out.extend((int as u16).to_be_bytes());
+			}
+			0x10000..0x1_0000_0000 => {
+				out.push(0xce);
+				out.extend((int as u32).to_be_bytes());
+			}
+			0x1_0000_0000.. => {
+				out.push(0xcf);
+				out.extend((int as u64).to_be_bytes());
+			}
+			-0x80..-0x20 => out.extend([0xd0, int as u8]),
+			-0x8000..-0x80 => {
+				out.push(0xd1);
+				out.extend((int as i16).to_be_bytes());
+			}
+			-0x8000_0000..-0x8000 => {
+				out.push(0xd2);
+				out.extend((int as i32).to_be_bytes());
+			}
+			_ => {
+				out.push(0xd3);
+				out.extend((int as i64).to_be_bytes());
+			}
+		}
+	} else if num as f32 as f64 == num {
+		out.push(0xca);
+		out.extend((num as f32).to_bits().to_be_bytes());
+	} else {
+		out.push(0xcb);
+		out.extend(num.to_bits().to_be_bytes());
+	}
+}
+
+pub fn from_slice(bytes: &[u8]) -> Result<JsonValue, Error> {
+	from_slice_with(bytes, &ParseLimits::default())
+}
+
+pub fn from_slice_with(bytes: &[u8], limits: &ParseLimits) -> Result<JsonValue, Error> {
+	struct Container {
+		value: JsonValue,
+		remaining: usize,
+		key: Option<String>,
+	}
+
+	if bytes.len() > limits.max_bytes {
+		return Err(ParseError {
+			kind: Limit::Bytes.into(),
+			offset: limits.max_bytes,
+		}
+		.into());
+	}
+
+	let mut i = 0;
+	let result = (|| -> Result<JsonValue, ParseErrorKind> {
+		let mut stack: Vec<Container> = vec![];
+		let mut start = 0;
+		let read = |i: &mut usize, len: usize| -> Result<u64, ParseErrorKind> {
+			let arg = bytes.get(*i..*i + len).ok_or("unexpected end of input")?;
+			*i += len;
+			Ok(arg.iter().fold(0, |acc, &b| acc << 8 | b as u64))
+		};
+
+		loop {
+			let next = match stack.last() {
+				Some(Container { remaining: 0, .. }) => stack.pop().unwrap().value,
+				_ => {
+					start = i;
+					let marker = *bytes.get(i).ok_or("unexpected end of input")?;
+					i += 1;
+
+					match marker {
+						0x80..=0x9f | 0xdc..=0xdf => {
+							let len = match marker {
+								0xdc | 0xde => read(&mut i, 2)?,
+								0xdd | 0xdf => read(&mut i, 4)?,
+								_ => (marker & 0x0f) as u64,
+							};
+							let is_object = matches!(marker, 0x80..=0x8f | 0xde | 0xdf);
+							if stack.len() >= limits.max_depth {
+								Err(Limit::Depth)?
+							}
+							stack.push(Container {
+								value: if is_object {
+									JsonValue::object()
+								} else {
+									JsonValue::list()
+								},
+								remaining: (len * if is_object { 2 } else { 1 }) as usize,
+								key: None,
+							});
+							continue;
+						}
+						0x00..=0x7f => JsonValue::from(marker as f64),
+						0xe0..=0xff => JsonValue::from(marker as i8 as f64),
+						0xc0 => JsonValue::Null,
+						0xc2 => JsonValue::Boolean(false),
+						0xc3 => JsonValue::Boolean(true),
+						0xcc..=0xcf => JsonValue::from(read(&mut i, 1 << (marker - 0xcc))? as f64),
+						0xd0 => JsonValue::from(read(&mut i, 1)? as i8 as f64),
+						0xd1 => JsonValue::from(read(&mut i, 2)? as i16 as f64),
+						0xd2 => JsonValue::from(read(&mut i, 4)? as i32 as f64),
+						0xd3 => JsonValue::from(read(&mut i, 8)? as i64 as f64),
+						0xca => JsonValue::from(f32::from_bits(read(&mut i, 4)? as u32) as f64),
+						0xcb => JsonValue::from(f64::from_bits(read(&mut i, 8)?)),
+						0xa0..=0xbf | 0xd9..=0xdb => {
+							let len = match marker {
+								0xd9..=0xdb => read(&mut i, 1 << (marker - 0xd9))? as usize,
+								_ => (marker & 0x1f) as usize,
+							};
+							if len > limits.max_string_len {
+								Err(Limit::StringLength)?
+							}
+							let end = i.checked_add(len).ok_or("invalid length")?;
+							let s = bytes.get(i..end).ok_or("unexpected end of input")?;
+							let s = std::str::from_utf8(s).map_err(|_| "invalid UTF-8")?;
+							i = end;
+							JsonValue::String(s.into())
+						}
+						0xc4..=0xc6 => Err("binary data is not supported")?,
+						0xc7..=0xc9 | 0xd4..=0xd8 => Err("extension types are not supported")?,
+						_ => Err("invalid marker byte")?,
+					}
+				}
+			};
+
+			let Some(parent) = stack.last_mut() else {
+				return match i == bytes.len() {
+					true => Ok(next),
+					false => Err("unexpected trailing bytes".into()),
+				};
+			};
+			parent.remaining -= 1;
+			match (&mut parent.value, parent.key.take()) {
+				(JsonValue::List(ls), _) if ls.len() >= limits.max_entries => Err(Limit::Entries)?,
+				(JsonValue::List(ls), _) => ls.push(next),
+				(JsonValue::Object(_), None) => match String::try_from(next) {
+					Ok(key) => parent.key = Some(key),
+					Err(_) => {
+						i = start;
+						Err("map keys must be strings")?
+					}
+				},
+				(JsonValue::Object(obj), Some(_)) if obj.len() >= limits.max_entries => {
+					Err(Limit::Entries)?
+				}
+				(JsonValue::Object(obj), Some(key)) => {
+					obj.insert(key, next);
+				}
+				_ => unreachable!(),
+			}
+		}
+	})();
+
+	result.map_err(|kind| ParseError { kind, offset: i }.into())
+}
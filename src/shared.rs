@@ -0,0 +1,207 @@
+use crate::{
+	Builder, Error, FiniteF64, HashMap, JsonValue, Node, ParseOptions, Tree, build_from, impl_tree,
+	parse_all, serialize, str_key, tree_eq,
+};
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::mem::take;
+use std::str::FromStr;
+use std::sync::Arc;
+
+// A value that's cheap to clone and share between threads. Strings, lists and objects
+// sit behind an Arc, so a clone only bumps a reference count. Mutating through as_list_mut,
+// as_object_mut, get_mut or indexing copies a container first if anything else still refers to
+// it, which means an update copies the containers along its path and shares everything else.
+#[derive(Clone)]
+pub enum SharedJson {
+	Null,
+	Boolean(bool),
+	Number(FiniteF64),
+	String(Arc<str>),
+	List(Arc<Vec<SharedJson>>),
+	Object(Arc<HashMap<String, SharedJson>>),
+}
+
+impl SharedJson {
+	pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Self, Error> {
+		parse_all(input, options).map_err(Error::from)
+	}
+
+	pub fn as_list(&self) -> Option<&Vec<SharedJson>> {
+		match self {
+			SharedJson::List(ls) => Some(ls),
+			_ => None,
+		}
+	}
+
+	pub fn as_list_mut(&mut self) -> Option<&mut Vec<SharedJson>> {
+		match self {
+			SharedJson::List(ls) => Some(Arc::make_mut(ls)),
+			_ => None,
+		}
+	}
+
+	pub fn as_object(&self) -> Option<&HashMap<String, SharedJson>> {
+		match self {
+			SharedJson::Object(obj) => Some(obj),
+			_ => None,
+		}
+	}
+
+	pub fn as_object_mut(&mut self) -> Option<&mut HashMap<String, SharedJson>> {
+		match self {
+			SharedJson::Object(obj) => Some(Arc::make_mut(obj)),
+			_ => None,
+		}
+	}
+
+	pub fn to_value(&self) -> JsonValue {
+		build_from(self)
+	}
+}
+
+impl_tree!(SharedJson);
+
+impl PartialEq for SharedJson {
+	fn eq(&self, rhs: &Self) -> bool {
+		tree_eq(self, rhs)
+	}
+}
+
+impl Eq for SharedJson {}
+
+impl FromStr for SharedJson {
+	type Err = Error;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		SharedJson::parse_with(input, &ParseOptions::default())
+	}
+}
+
+impl From<&JsonValue> for SharedJson {
+	fn from(value: &JsonValue) -> Self {
+		build_from(value)
+	}
+}
+
+impl From<JsonValue> for SharedJson {
+	fn from(value: JsonValue) -> Self {
+		build_from(&value)
+	}
+}
+
+impl From<&SharedJson> for JsonValue {
+	fn from(value: &SharedJson) -> Self {
+		value.to_value()
+	}
+}
+
+impl Tree for SharedJson {
+	type ListIter<'v> = std::slice::Iter<'v, SharedJson>;
+	type ObjectIter<'v> = std::iter::Map<
+		std::collections::hash_map::Iter<'v, String, SharedJson>,
+		fn((&'v String, &'v SharedJson)) -> (&'v str, &'v SharedJson),
+	>;
+
+	fn node(&self) -> Node<'_, Self> {
+		match self {
+			SharedJson::Null => Node::Null,
+			SharedJson::Boolean(b) => Node::Boolean(*b),
+			SharedJson::Number(num) => Node::Number(num.into()),
+			SharedJson::String(s) => Node::String(s),
+			SharedJson::List(ls) => Node::List(ls.iter()),
+			SharedJson::Object(obj) => Node::Object(obj.iter().map(str_key as _)),
+		}
+	}
+
+	fn items(&self) -> Option<&[Self]> {
+		Some(self.as_list()?)
+	}
+
+	fn items_mut(&mut self) -> Option<&mut [Self]> {
+		Some(self.as_list_mut()?)
+	}
+
+	fn lookup(&self, key: &str) -> Option<&Self> {
+		self.as_object()?.get(key)
+	}
+
+	fn lookup_mut(&mut self, key: &str) -> Option<&mut Self> {
+		self.as_object_mut()?.get_mut(key)
+	}
+
+	fn entry(&mut self, key: &str) -> Option<&mut Self> {
+		let obj = self.as_object_mut()?;
+		Some(obj.entry(key.into()).or_insert(SharedJson::Null))
+	}
+
+	// Only moves out of containers nothing else refers to
+	fn take_children(&mut self, out: &mut Vec<Self>) {
+		match self {
+			SharedJson::List(ls) => {
+				if let Some(ls) = Arc::get_mut(ls) {
+					out.append(ls);
+				}
+			}
+			SharedJson::Object(obj) => {
+				if let Some(obj) = Arc::get_mut(obj) {
+					out.extend(take(obj).into_values());
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+// While a value is being built nothing else refers to it, so make_mut never copies
+impl<'a> Builder<'a> for SharedJson {
+	type Keys = ();
+
+	fn null() -> Self {
+		SharedJson::Null
+	}
+
+	fn boolean(val: bool) -> Self {
+		SharedJson::Boolean(val)
+	}
+
+	fn number(val: f64) -> Self {
+		FiniteF64::try_from(val).map_or(SharedJson::Null, SharedJson::Number)
+	}
+
+	fn string(val: Cow<'a, str>) -> Self {
+		SharedJson::String(val.into())
+	}
+
+	fn list() -> Self {
+		SharedJson::List(Arc::default())
+	}
+
+	fn object() -> Self {
+		SharedJson::Object(Arc::default())
+	}
+
+	fn push_value(&mut self, val: Self) {
+		if let Some(ls) = self.as_list_mut() {
+			ls.push(val);
+		}
+	}
+
+	fn insert_value(&mut self, _: &mut (), key: Cow<'a, str>, val: Self) {
+		if let Some(obj) = self.as_object_mut() {
+			obj.insert(key.into_owned(), val);
+		}
+	}
+}
+
+impl Display for SharedJson {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, false)
+	}
+}
+
+impl Debug for SharedJson {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		serialize(self, f, true)
+	}
+}
@@ -0,0 +1,239 @@
+use crate::{Error, JsonValue, ParseError, ParseOptions};
+use std::borrow::Cow;
+use std::io::{self, Read};
+use std::mem::take;
+
+const RECORD_SEPARATOR: char = '\x1e';
+const CHUNK: usize = 8192;
+
+pub struct StreamDeserializer<'a> {
+	input: Cow<'a, str>,
+	offset: usize,
+	// Input that has been parsed and dropped from the front of the buffer
+	consumed: usize,
+	reader: Option<Box<dyn Read + 'a>>,
+	// The start of a character that was split between two reads
+	partial: Vec<u8>,
+	options: ParseOptions,
+	json_seq: bool,
+}
+
+impl<'a> StreamDeserializer<'a> {
+	pub fn new(input: &'a str) -> Self {
+		StreamDeserializer {
+			input: Cow::Borrowed(input),
+			offset: 0,
+			consumed: 0,
+			reader: None,
+			partial: vec![],
+			options: ParseOptions::default(),
+			json_seq: false,
+		}
+	}
+
+	// Reads only as far as the next value (or record) reaches, so values come out as soon as
+	// they've arrived and the buffer never holds much more than the largest of them
+	pub fn from_reader(reader: impl Read + 'a) -> Self {
+		StreamDeserializer {
+			input: Cow::Owned(String::new()),
+			reader: Some(Box::new(reader)),
+			..StreamDeserializer::new("")
+		}
+	}
+
+	pub fn options(mut self, options: ParseOptions) -> Self {
+		self.options = options;
+		self
+	}
+
+	// Parse RFC 7464 JSON text sequences, where every value is preceded by an RS character
+	pub fn json_seq(mut self, json_seq: bool) -> Self {
+		self.json_seq = json_seq;
+		self
+	}
+
+	pub fn byte_offset(&self) -> usize {
+		self.consumed + self.offset
+	}
+
+	// Appends the next read to the buffer, after dropping what's been parsed. Each read is at
+	// least as long as the text still waiting for the rest of its value, which keeps the total
+	// work of rescanning that text linear.
+	fn fill(&mut self) -> Result<(), Error> {
+		let Some(reader) = &mut self.reader else {
+			return Ok(());
+		};
+		let input = self.input.to_mut();
+		input.drain(..self.offset);
+		self.consumed += self.offset;
+		self.offset = 0;
+
+		let mut bytes = take(&mut self.partial);
+		let start = bytes.len();
+		bytes.resize(start + input.len().max(CHUNK), 0);
+		let read = loop {
+			match reader.read(&mut bytes[start..]) {
+				Ok(read) => break read,
+				Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+				Err(err) => return Err(err.into()),
+			}
+		};
+		bytes.truncate(start + read);
+		if read == 0 {
+			self.reader = None;
+		}
+
+		let valid = match std::str::from_utf8(&bytes) {
+			Ok(text) => text.len(),
+			// Only a character cut off at the end is completed by the next read
+			Err(err) if err.error_len().is_none() && read > 0 => err.valid_up_to(),
+			Err(_) => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					"stream did not contain valid UTF-8",
+				)
+				.into());
+			}
+		};
+		input.push_str(std::str::from_utf8(&bytes[..valid]).unwrap());
+		self.partial = bytes.split_off(valid);
+		Ok(())
+	}
+
+	fn next_value(&mut self) -> Option<Result<JsonValue, Error>> {
+		let start = loop {
+			let rest = &self.input[self.offset..];
+			let start =
+				self.offset + rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+			// Until the whole value has arrived, an error might only mean that a read cut it off
+			if self.reader.is_none() || is_complete(&self.input[start..]) {
+				break start;
+			}
+			// Whitespace is dropped too, so waiting through lots of it doesn't grow the buffer
+			self.offset = start;
+			if let Err(err) = self.fill() {
+				self.offset = self.input.len();
+				self.reader = None;
+				return Some(Err(err));
+			}
+		};
+		if start == self.input.len() {
+			self.offset = start;
+			return None;
+		}
+
+		match crate::parse_prefix(&self.input[start..], &self.options) {
+			Ok((value, len)) => {
+				self.offset = start + len;
+				Some(Ok(value))
+			}
+			Err(err) => {
+				self.offset = self.input.len();
+				self.reader = None;
+				Some(Err(ParseError {
+					offset: self.consumed + start + err.offset,
+					..err
+				}
+				.into()))
+			}
+		}
+	}
+
+	fn next_record(&mut self) -> Option<Result<JsonValue, Error>> {
+		loop {
+			let rest = &self.input[self.offset..];
+			// A record runs up to the next separator, so wait for that or the end of the reader
+			let next = rest.trim_start_matches(RECORD_SEPARATOR);
+			if self.reader.is_some() && !next.contains(RECORD_SEPARATOR) {
+				if let Err(err) = self.fill() {
+					self.offset = self.input.len();
+					self.reader = None;
+					return Some(Err(err));
+				}
+				continue;
+			}
+			if rest.is_empty() {
+				return None;
+			}
+
+			let has_separator = rest.starts_with(RECORD_SEPARATOR);
+			let record = rest.trim_start_matches(RECORD_SEPARATOR);
+			let start = self.offset + rest.len() - record.len();
+			let end = record
+				.find(RECORD_SEPARATOR)
+				.map_or(self.input.len(), |pos| start + pos);
+			let record = &self.input[start..end];
+			self.offset = end;
+
+			let text = record.trim_matches([' ', '\t', '\n', '\r']);
+			let text_start =
+				start + record.len() - record.trim_start_matches([' ', '\t', '\n', '\r']).len();
+			if text.is_empty() {
+				continue;
+			}
+			if !has_separator {
+				return Some(Err(Error::Syntax {
+					message: "missing record separator".into(),
+					offset: self.consumed + text_start,
+				}));
+			}
+
+			let truncated = !record.ends_with([' ', '\t', '\n', '\r']);
+			return Some(match crate::parse_all(text, &self.options) {
+				Ok(JsonValue::Number(_)) if truncated => Err(Error::Syntax {
+					message: "possibly truncated number".into(),
+					offset: self.consumed + text_start,
+				}),
+				Ok(value) => Ok(value),
+				Err(err) => Err(ParseError {
+					offset: self.consumed + text_start + err.offset,
+					..err
+				}
+				.into()),
+			});
+		}
+	}
+}
+
+impl Iterator for StreamDeserializer<'_> {
+	type Item = Result<JsonValue, Error>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.json_seq {
+			self.next_record()
+		} else {
+			self.next_value()
+		}
+	}
+}
+
+// Whether text starts with a whole value, judging only by brackets, quotes and the characters
+// that can end a number or literal. Whether it's valid is up to the parser.
+fn is_complete(text: &str) -> bool {
+	let mut depth = 0usize;
+	let mut in_string = false;
+	let mut escaped = false;
+	for (i, b) in text.bytes().enumerate() {
+		if in_string {
+			match b {
+				_ if escaped => escaped = false,
+				b'\\' => escaped = true,
+				b'"' if depth == 0 => return true,
+				b'"' => in_string = false,
+				_ => {}
+			}
+			continue;
+		}
+		if depth == 0 && i > 0 && !b.is_ascii_alphanumeric() && !matches!(b, b'+' | b'-' | b'.') {
+			return true;
+		}
+		match b {
+			b'"' => in_string = true,
+			b'[' | b'{' => depth += 1,
+			b']' | b'}' if depth <= 1 => return true,
+			b']' | b'}' => depth -= 1,
+			_ => {}
+		}
+	}
+	false
+}
@@ -1,4 +1,12 @@
-use crate::{HashMap, JsonValue, json};
+use crate::arbitrary::Generator;
+use crate::lines::{JsonLines, JsonLinesWriter};
+use crate::stream::StreamDeserializer;
+use crate::{
+	ArenaValue, BinaryOptions, DuplicateKeys, Error, FiniteF64, HashMap, InternedValue, JsonArena,
+	JsonKind, JsonObject, JsonPointer, JsonString, JsonValue, JsonValueRef, JsonView, KeyInterner,
+	Limit, ParseLimits, ParseOptions, SharedJson, TypeError, Visitor, json, parse_borrowed,
+};
+use std::borrow::Cow;
 
 #[test]
 fn test_null() {
@@ -52,6 +60,19 @@ fn test_complicated2() {
 	);
 }
 
+#[test]
+fn test_number_conversions() {
+	assert_eq!(
+		JsonValue::from(-234.43),
+		JsonValue::try_from(-234.43).unwrap()
+	);
+	assert_eq!(JsonValue::from(f64::NAN), JsonValue::Null);
+	assert_eq!(JsonValue::from(f64::NEG_INFINITY), JsonValue::Null);
+	let num: JsonValue = 0.184.into();
+	assert_eq!(f64::try_from(&num), Ok(0.184));
+	assert_eq!(JsonValue::from(4_i32), JsonValue::from(4_f64));
+}
+
 #[test]
 fn test_inf() {
 	assert_eq!(
@@ -60,6 +81,26 @@ fn test_inf() {
 	);
 }
 
+#[test]
+fn test_number_precision() {
+	let num = |input: &str| input.parse::<JsonValue>().map(|val| val.as_f64().unwrap());
+	assert_eq!(num("0.1"), Ok(0.1));
+	assert_eq!(num("0.30000000000000004"), Ok(0.1 + 0.2));
+	assert_eq!(num("1.7976931348623157e308"), Ok(f64::MAX));
+	assert_eq!(num("2.2250738585072014e-308"), Ok(f64::MIN_POSITIVE));
+	assert_eq!(num("5e-324"), Ok(5e-324));
+	assert_eq!(num("-9007199254740993"), Ok(-9007199254740992.));
+	assert_eq!(num("9007199254740995"), Ok(9007199254740996.));
+	assert_eq!(num("-0").map(f64::is_sign_negative), Ok(true));
+	assert_eq!(num("1e-400"), Ok(0.));
+
+	// The lenient forms from_str has always accepted still parse the same way
+	assert_eq!(num("1."), Ok(1.));
+	assert_eq!(num("1.e2"), Ok(100.));
+	assert_eq!(num("2e+"), Ok(2.));
+	assert_eq!(num("-2.5E-"), Ok(-2.5));
+}
+
 #[test]
 fn test_empty_string() {
 	assert_eq!(
@@ -158,7 +199,7 @@ fn test_complex_list() {
 fn test_obj() {
 	assert_eq!(
 		"{\"hi\": 5.1}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([(
+		Ok(JsonValue::from(HashMap::from([(
 			"hi".into(),
 			JsonValue::Number(5.1_f64.try_into().unwrap())
 		)])))
@@ -169,9 +210,9 @@ fn test_obj() {
 fn test_nested_obj() {
 	assert_eq!(
 		"{\"outer\": {\"inner\": 42}}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([(
+		Ok(JsonValue::from(HashMap::from([(
 			"outer".into(),
-			JsonValue::Object(HashMap::from([(
+			JsonValue::from(HashMap::from([(
 				"inner".into(),
 				JsonValue::Number(42.0_f64.try_into().unwrap())
 			)]))
@@ -183,7 +224,7 @@ fn test_nested_obj() {
 fn test_large_obj() {
 	assert_eq!(
 		"{\"first\": 1, \"second\": 2}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([
+		Ok(JsonValue::from(HashMap::from([
 			("first".into(), JsonValue::Number(1_f64.try_into().unwrap())),
 			(
 				"second".into(),
@@ -197,10 +238,10 @@ fn test_large_obj() {
 fn test_complex_nested_json() {
 	assert_eq!(
 		"{\"level1\": {\"level2\": {\"level3\": {\"num\": 123, \"text\": \"hello\", \"array\": [true, false, null, 3.15], \"obj\": {\"key\": \"value\"}}}}}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([
-			("level1".into(), JsonValue::Object(HashMap::from([
-				("level2".into(), JsonValue::Object(HashMap::from([
-					("level3".into(), JsonValue::Object(HashMap::from([
+		Ok(JsonValue::from(HashMap::from([
+			("level1".into(), JsonValue::from(HashMap::from([
+				("level2".into(), JsonValue::from(HashMap::from([
+					("level3".into(), JsonValue::from(HashMap::from([
 						("num".into(), JsonValue::Number(123.0_f64.try_into().unwrap())),
 						("text".into(), JsonValue::String("hello".into())),
 						("array".into(), JsonValue::List(vec![
@@ -209,7 +250,7 @@ fn test_complex_nested_json() {
 							JsonValue::Null,
 							JsonValue::Number(3.15_f64.try_into().unwrap())
 						])),
-						("obj".into(), JsonValue::Object(HashMap::from([
+						("obj".into(), JsonValue::from(HashMap::from([
 						("key".into(), JsonValue::String("value".into()))
 						])))
 					])))
@@ -223,17 +264,17 @@ fn test_complex_nested_json() {
 fn test_deeply_nested_json() {
 	assert_eq!(
 		"{\"a\": {\"b\": {\"c\": {\"d\": {\"e\": {\"f\": 42}}}}}}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([(
+		Ok(JsonValue::from(HashMap::from([(
 			"a".into(),
-			JsonValue::Object(HashMap::from([(
+			JsonValue::from(HashMap::from([(
 				"b".into(),
-				JsonValue::Object(HashMap::from([(
+				JsonValue::from(HashMap::from([(
 					"c".into(),
-					JsonValue::Object(HashMap::from([(
+					JsonValue::from(HashMap::from([(
 						"d".into(),
-						JsonValue::Object(HashMap::from([(
+						JsonValue::from(HashMap::from([(
 							"e".into(),
-							JsonValue::Object(HashMap::from([(
+							JsonValue::from(HashMap::from([(
 								"f".into(),
 								JsonValue::Number(42.0_f64.try_into().unwrap())
 							)]))
@@ -249,7 +290,7 @@ fn test_deeply_nested_json() {
 fn test_mixed_data_types_json() {
 	assert_eq!(
 		"{\"string\": \"example\", \"boolean\": true, \"null_value\": null, \"number\": 99.98, \"list\": [1, \"two\", false, {\"nested\": \"yes\"}]}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([
+		Ok(JsonValue::from(HashMap::from([
 			("string".into(), JsonValue::String("example".into())),
 			("boolean".into(), JsonValue::Boolean(true)),
 			("null_value".into(), JsonValue::Null),
@@ -258,7 +299,7 @@ fn test_mixed_data_types_json() {
 				JsonValue::Number(1.0_f64.try_into().unwrap()),
 				JsonValue::String("two".into()),
 				JsonValue::Boolean(false),
-				JsonValue::Object(HashMap::from([
+				JsonValue::from(HashMap::from([
 					("nested".into(), JsonValue::String("yes".into()))
 				]))
 			]))
@@ -270,7 +311,7 @@ fn test_mixed_data_types_json() {
 fn test_empty_json_object() {
 	assert_eq!(
 		"{}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::new()))
+		Ok(JsonValue::from(HashMap::new()))
 	);
 }
 
@@ -283,12 +324,12 @@ fn test_empty_json_array() {
 fn test_nested_empty_structures() {
 	assert_eq!(
 		"{\"a\": {}, \"b\": [], \"c\": {\"d\": []}}".parse::<JsonValue>(),
-		Ok(JsonValue::Object(HashMap::from([
-			("a".into(), JsonValue::Object(HashMap::new())),
+		Ok(JsonValue::from(HashMap::from([
+			("a".into(), JsonValue::from(HashMap::new())),
 			("b".into(), JsonValue::List(vec![])),
 			(
 				"c".into(),
-				JsonValue::Object(HashMap::from([("d".into(), JsonValue::List(vec![]))]))
+				JsonValue::from(HashMap::from([("d".into(), JsonValue::List(vec![]))]))
 			)
 		])))
 	);
@@ -408,16 +449,16 @@ fn test_super_complex_nested_json() {
 	let mut deeper_object: HashMap<String, JsonValue> = HashMap::new();
 	deeper_object.insert(
 		"deeper".into(),
-		JsonValue::List(vec![JsonValue::Null, JsonValue::Object(inner_most_object)]),
+		JsonValue::List(vec![JsonValue::Null, JsonValue::from(inner_most_object)]),
 	);
 
 	let deep_array = JsonValue::List(vec![
-		JsonValue::Object(HashMap::from([("deep".into(), JsonValue::Boolean(true))])),
-		JsonValue::Object(HashMap::from([(
+		JsonValue::from(HashMap::from([("deep".into(), JsonValue::Boolean(true))])),
+		JsonValue::from(HashMap::from([(
 			"deeper".into(),
 			JsonValue::List(vec![
 				JsonValue::Null,
-				JsonValue::Object(HashMap::from([(
+				JsonValue::from(HashMap::from([(
 					"even_deeper".into(),
 					JsonValue::String("end".into()),
 				)])),
@@ -426,11 +467,11 @@ fn test_super_complex_nested_json() {
 	]);
 
 	let level1_array = JsonValue::List(vec![
-		JsonValue::Object(HashMap::from([(
+		JsonValue::from(HashMap::from([(
 			"key1".into(),
 			JsonValue::String("value1".into()),
 		)])),
-		JsonValue::Object(HashMap::from([(
+		JsonValue::from(HashMap::from([(
 			"key2".into(),
 			JsonValue::Number(2.0_f64.try_into().unwrap()),
 		)])),
@@ -444,12 +485,12 @@ fn test_super_complex_nested_json() {
 	let mut inner_obj = HashMap::new();
 	inner_obj.insert("d".into(), JsonValue::String("deepest".into()));
 	let mut inner_array_object3 = HashMap::new();
-	inner_array_object3.insert("c".into(), JsonValue::Object(inner_obj));
+	inner_array_object3.insert("c".into(), JsonValue::from(inner_obj));
 
 	let inner_array = JsonValue::List(vec![
-		JsonValue::Object(inner_array_object),
-		JsonValue::Object(inner_array_object2),
-		JsonValue::Object(inner_array_object3),
+		JsonValue::from(inner_array_object),
+		JsonValue::from(inner_array_object2),
+		JsonValue::from(inner_array_object3),
 	]);
 
 	let mut nested3 = HashMap::new();
@@ -462,31 +503,31 @@ fn test_super_complex_nested_json() {
 	nested3.insert("inner_array".into(), inner_array);
 
 	let mut nested2 = HashMap::new();
-	nested2.insert("nested3".into(), JsonValue::Object(nested3));
+	nested2.insert("nested3".into(), JsonValue::from(nested3));
 
 	let mut nested1 = HashMap::new();
-	nested1.insert("nested2".into(), JsonValue::Object(nested2));
+	nested1.insert("nested2".into(), JsonValue::from(nested2));
 
 	let mut object = HashMap::new();
-	object.insert("nested1".into(), JsonValue::Object(nested1));
+	object.insert("nested1".into(), JsonValue::from(nested1));
 
 	let mut level1_object = HashMap::new();
 	level1_object.insert("array".into(), level1_array);
-	level1_object.insert("object".into(), JsonValue::Object(object));
+	level1_object.insert("object".into(), JsonValue::from(object));
 	level1_object.insert("simple".into(), JsonValue::String("test".into()));
 
 	let root_array = JsonValue::List(vec![
 		JsonValue::Number(1.0_f64.try_into().unwrap()),
 		JsonValue::Number(2.0_f64.try_into().unwrap()),
 		JsonValue::Number(3.0_f64.try_into().unwrap()),
-		JsonValue::Object(HashMap::from([(
+		JsonValue::from(HashMap::from([(
 			"final".into(),
 			JsonValue::String("object".into()),
 		)])),
 	]);
 
-	let expected = JsonValue::Object(HashMap::from([
-		("level1".into(), JsonValue::Object(level1_object)),
+	let expected = JsonValue::from(HashMap::from([
+		("level1".into(), JsonValue::from(level1_object)),
 		("root_array".into(), root_array),
 	]));
 
@@ -621,3 +662,2158 @@ fn test_massive_object() {
 	assert_eq!(json, json.clone());
 	assert_eq!(json, json.to_string().parse::<JsonValue>().unwrap());
 }
+
+#[test]
+fn lines_read() {
+	let input = "{\"a\": 1}\n[true, null]\r\n\"str\"\n";
+	let values: Vec<_> = JsonLines::new(input.as_bytes())
+		.collect::<Result<_, _>>()
+		.unwrap();
+	assert_eq!(
+		values,
+		vec![json!({"a": 1}), json!([true, null]), json!("str")]
+	);
+}
+
+#[test]
+fn lines_blank() {
+	let input = "1\n\n  \n2";
+	assert!(matches!(
+		JsonLines::new(input.as_bytes()).nth(1),
+		Some(Err(Error::Line { line: 2, .. }))
+	));
+
+	let values: Vec<_> = JsonLines::new(input.as_bytes())
+		.skip_blank_lines(true)
+		.collect::<Result<_, _>>()
+		.unwrap();
+	assert_eq!(values, vec![json!(1), json!(2)]);
+}
+
+#[test]
+fn lines_bad_line() {
+	let input = "1\n{oops\n3\n";
+	let results: Vec<_> = JsonLines::new(input.as_bytes()).collect();
+	assert_eq!(results.len(), 2);
+	assert!(matches!(results[1], Err(Error::Line { line: 2, .. })));
+
+	let results: Vec<_> = JsonLines::new(input.as_bytes())
+		.continue_on_error(true)
+		.collect();
+	assert_eq!(results.len(), 3);
+	assert!(results[1].is_err());
+	assert_eq!(results[2].as_ref().unwrap(), &json!(3));
+}
+
+#[test]
+fn lines_write() {
+	let values = [json!({"multi\nline": [1, 2]}), json!(null), json!("x\ny")];
+	let mut writer = JsonLinesWriter::new(Vec::new());
+	writer.write_all(&values).unwrap();
+	let output = String::from_utf8(writer.into_inner()).unwrap();
+
+	assert_eq!(output.lines().count(), 3);
+	let read: Vec<_> = JsonLines::new(output.as_bytes())
+		.collect::<Result<_, _>>()
+		.unwrap();
+	assert_eq!(read, values);
+}
+
+#[test]
+fn stream_concatenated() {
+	let mut stream = StreamDeserializer::new("{\"a\":1}{\"a\":2} [3]\n\"four\"true 5 ");
+	assert_eq!(stream.next(), Some(Ok(json!({"a": 1}))));
+	assert_eq!(stream.byte_offset(), 7);
+	assert_eq!(stream.next(), Some(Ok(json!({"a": 2}))));
+	assert_eq!(stream.byte_offset(), 14);
+	assert_eq!(stream.next(), Some(Ok(json!([3]))));
+	assert_eq!(stream.next(), Some(Ok(json!("four"))));
+	assert_eq!(stream.next(), Some(Ok(json!(true))));
+	assert_eq!(stream.next(), Some(Ok(json!(5))));
+	assert_eq!(stream.byte_offset(), 31);
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn stream_error() {
+	let mut stream = StreamDeserializer::new("[1] [2, ] [3]");
+	assert_eq!(stream.next(), Some(Ok(json!([1]))));
+	assert!(stream.next().unwrap().is_err());
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn stream_limits_each_value() {
+	let options = ParseOptions {
+		limits: ParseLimits {
+			max_bytes: 10,
+			..Default::default()
+		},
+		..Default::default()
+	};
+	let values: Result<Vec<_>, _> = StreamDeserializer::new("[1,2,3] [4,5,6]\n[7,8,9]")
+		.options(options.clone())
+		.collect();
+	assert_eq!(
+		values,
+		Ok(vec![json!([1, 2, 3]), json!([4, 5, 6]), json!([7, 8, 9])])
+	);
+
+	let mut stream = StreamDeserializer::new("[1] [1,2,3,4,5] [2]").options(options.clone());
+	assert_eq!(stream.next(), Some(Ok(json!([1]))));
+	assert!(matches!(
+		stream.next(),
+		Some(Err(Error::LimitExceeded {
+			limit: Limit::Bytes,
+			offset: 14
+		}))
+	));
+
+	let mut records = StreamDeserializer::new("\x1e[1,2,3]\n\x1e[4,5,6]\n")
+		.json_seq(true)
+		.options(options);
+	assert_eq!(records.next(), Some(Ok(json!([1, 2, 3]))));
+	assert_eq!(records.next(), Some(Ok(json!([4, 5, 6]))));
+}
+
+#[test]
+fn stream_from_reader() {
+	use std::io::Read;
+
+	let stream = StreamDeserializer::from_reader("1 2 3".as_bytes());
+	let values: Vec<_> = stream.collect::<Result<_, _>>().unwrap();
+	assert_eq!(values, vec![json!(1), json!(2), json!(3)]);
+
+	// Values come out before the reader ends
+	let endless = "{\"a\": [1]} 22 ".as_bytes().chain(std::io::repeat(b' '));
+	let mut stream = StreamDeserializer::from_reader(endless);
+	assert_eq!(stream.next(), Some(Ok(json!({"a": [1]}))));
+	assert_eq!(stream.next(), Some(Ok(json!(22))));
+	assert_eq!(stream.byte_offset(), 13);
+
+	// A byte at a time, every value and error matches parsing the whole input
+	struct Trickle<'a>(&'a [u8]);
+	impl Read for Trickle<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			let Some((first, rest)) = self.0.split_first() else {
+				return Ok(0);
+			};
+			buf[0] = *first;
+			self.0 = rest;
+			Ok(1)
+		}
+	}
+	for (input, json_seq) in [
+		(
+			"{\"a\":1}{\"b\":\"]}\\\"é\"}[true] 12 -3.5e2\"日本\" nul",
+			false,
+		),
+		("[1, 2] [3,, 4] 5", false),
+		(
+			"\x1e{\"a\":1}\n\x1e\x1e[true]\n\x1e{\"broken\n\x1e42\n\x1e7",
+			true,
+		),
+	] {
+		let offsets = |mut stream: StreamDeserializer| {
+			std::iter::from_fn(move || Some((stream.next()?, stream.byte_offset())))
+				// Only the string knows where the input ends after an error
+				.map(|(value, offset)| match value {
+					Ok(value) => (Ok(value), offset),
+					Err(err) => (Err(err.to_string()), 0),
+				})
+				.collect::<Vec<_>>()
+		};
+		let expected = offsets(StreamDeserializer::new(input).json_seq(json_seq));
+		let reader = Trickle(input.as_bytes());
+		assert_eq!(
+			offsets(StreamDeserializer::from_reader(reader).json_seq(json_seq)),
+			expected,
+			"{input:?}"
+		);
+	}
+
+	let err = StreamDeserializer::from_reader(&b"1 \xff"[..])
+		.next()
+		.unwrap();
+	assert!(matches!(err, Err(Error::Io(_))));
+}
+
+#[test]
+fn stream_json_seq() {
+	let input = "\x1e{\"a\":1}\n\x1e\x1e[true]\n\x1e{\"broken\n\x1e42\n\x1e7";
+	let mut stream = StreamDeserializer::new(input).json_seq(true);
+	assert_eq!(stream.next(), Some(Ok(json!({"a": 1}))));
+	assert_eq!(stream.byte_offset(), 9);
+	assert_eq!(stream.next(), Some(Ok(json!([true]))));
+	assert!(stream.next().unwrap().is_err());
+	assert_eq!(stream.next(), Some(Ok(json!(42))));
+	assert!(stream.next().unwrap().is_err());
+	assert_eq!(stream.next(), None);
+	assert_eq!(stream.byte_offset(), input.len());
+}
+
+#[test]
+fn stream_json_seq_missing_separator() {
+	let mut stream = StreamDeserializer::new("1\n\x1e2\n").json_seq(true);
+	assert!(stream.next().unwrap().is_err());
+	assert_eq!(stream.next(), Some(Ok(json!(2))));
+}
+
+#[test]
+fn test_error_trailing_value() {
+	assert!("truetrue".parse::<JsonValue>().is_err());
+	assert!("[][]".parse::<JsonValue>().is_err());
+}
+
+fn parse_limited(input: &str, limits: ParseLimits) -> Result<JsonValue, Limit> {
+	let options = ParseOptions {
+		limits,
+		..Default::default()
+	};
+	JsonValue::parse_with(input, &options).map_err(|err| match err {
+		Error::LimitExceeded { limit, .. } => limit,
+		err => panic!("unexpected error: {err}"),
+	})
+}
+
+#[test]
+fn limit_depth() {
+	let limits = ParseLimits {
+		max_depth: 3,
+		..Default::default()
+	};
+	assert!(parse_limited("[{\"a\": [1]}]", limits).is_ok());
+	assert_eq!(parse_limited("[{\"a\": [[1]]}]", limits), Err(Limit::Depth));
+}
+
+#[test]
+fn limit_bytes() {
+	let limits = ParseLimits {
+		max_bytes: 8,
+		..Default::default()
+	};
+	assert!(parse_limited("[1, 2, 3]", limits).is_err());
+	assert_eq!(parse_limited("[1,2,3]", limits), Ok(json!([1, 2, 3])));
+}
+
+#[test]
+fn limit_string_len() {
+	let limits = ParseLimits {
+		max_string_len: 4,
+		..Default::default()
+	};
+	assert!(parse_limited("{\"abcd\": \"\\u0041bcd\"}", limits).is_ok());
+	assert_eq!(
+		parse_limited("\"abcd\\n\"", limits),
+		Err(Limit::StringLength)
+	);
+	assert_eq!(
+		parse_limited("{\"abcde\": 1}", limits),
+		Err(Limit::StringLength)
+	);
+}
+
+#[test]
+fn limit_entries() {
+	let limits = ParseLimits {
+		max_entries: 2,
+		..Default::default()
+	};
+	assert!(parse_limited("[[1, 2], {\"a\": 1, \"b\": 2}]", limits).is_ok());
+	assert_eq!(parse_limited("[1, 2, 3]", limits), Err(Limit::Entries));
+	assert_eq!(
+		parse_limited("{\"a\": 1, \"b\": 2, \"c\": 3}", limits),
+		Err(Limit::Entries)
+	);
+}
+
+#[test]
+fn limit_number_len() {
+	let limits = ParseLimits {
+		max_number_len: 6,
+		..Default::default()
+	};
+	assert!(parse_limited("[-1.5e3, 123456]", limits).is_ok());
+	assert_eq!(parse_limited("[1234567]", limits), Err(Limit::NumberLength));
+}
+
+#[test]
+fn error_offset() {
+	let err = "[1, 2, }".parse::<JsonValue>().unwrap_err();
+	assert_eq!(err.offset(), Some(7));
+	assert!(matches!(err, Error::Syntax { .. }));
+}
+
+fn parse_duplicates(input: &str, duplicate_keys: DuplicateKeys) -> Result<JsonValue, Error> {
+	let options = ParseOptions {
+		duplicate_keys,
+		..Default::default()
+	};
+	JsonValue::parse_with(input, &options)
+}
+
+#[test]
+fn duplicate_keys() {
+	let input = r#"{"a": 1, "b": [true], "a": 2, "c": {"a": 3}, "a": [4]}"#;
+	assert_eq!(
+		parse_duplicates(input, DuplicateKeys::LastWins),
+		Ok(json!({"a": [4], "b": [true], "c": {"a": 3}}))
+	);
+	assert_eq!(
+		parse_duplicates(input, DuplicateKeys::FirstWins),
+		Ok(json!({"a": 1, "b": [true], "c": {"a": 3}}))
+	);
+	assert_eq!(
+		parse_duplicates(input, DuplicateKeys::CollectIntoList),
+		Ok(json!({"a": [1, 2, [4]], "b": [true], "c": {"a": 3}}))
+	);
+	assert_eq!(
+		parse_duplicates(input, DuplicateKeys::Error),
+		Err(Error::DuplicateKey {
+			key: "a".into(),
+			offset: 22
+		})
+	);
+}
+
+#[test]
+fn duplicate_keys_nested() {
+	let input = r#"{"x": {"k": 1, "k": 2}, "y": {"k": 3}}"#;
+	assert_eq!(
+		parse_duplicates(input, DuplicateKeys::CollectIntoList),
+		Ok(json!({"x": {"k": [1, 2]}, "y": {"k": 3}}))
+	);
+	assert!(parse_duplicates(r#"{"k": {}, "j": {"k": 1}}"#, DuplicateKeys::Error).is_ok());
+}
+
+#[test]
+fn borrowed_strings() {
+	let input = r#"{"plain": "text", "esc\\aped": "line\nbreak", "list": ["a", "\u0042"]}"#;
+	let json = parse_borrowed(input).unwrap();
+
+	let JsonValueRef::Object(obj) = &json else {
+		panic!("expected an object");
+	};
+	assert!(
+		obj.keys()
+			.all(|key| matches!(key, Cow::Borrowed(_)) == !key.contains('\\'))
+	);
+
+	assert!(matches!(
+		&json["plain"],
+		JsonValueRef::String(Cow::Borrowed("text"))
+	));
+	assert!(
+		matches!(&json["esc\\aped"], JsonValueRef::String(Cow::Owned(s)) if s == "line\nbreak")
+	);
+	assert!(matches!(
+		&json["list"][0],
+		JsonValueRef::String(Cow::Borrowed("a"))
+	));
+	assert!(matches!(&json["list"][1], JsonValueRef::String(Cow::Owned(s)) if s == "B"));
+}
+
+#[test]
+fn borrowed_conversions() {
+	let input = r#"{"num": -1.5, "flag": true, "list": [1, 2], "obj": {}}"#;
+	let mut json = parse_borrowed(input).unwrap();
+
+	assert_eq!(f64::try_from(&json["num"]), Ok(-1.5));
+	assert_eq!(<&bool>::try_from(&json["flag"]), Ok(&true));
+	assert_eq!(<&Vec<_>>::try_from(&json["list"]).map(Vec::len), Ok(2));
+	assert!(<&HashMap<_, _>>::try_from(&json["list"]).is_err());
+
+	json["obj"]["new"] = JsonValueRef::String("value".into());
+	json["list"][0] = JsonValueRef::Null;
+	assert_eq!(
+		json.into_owned(),
+		json!({"num": (-1.5), "flag": true, "list": [null, 2], "obj": {"new": "value"}})
+	);
+}
+
+#[test]
+fn borrowed_matches_owned() {
+	let input = r#"{"a": [1, "two", {"three": null}], "b": "\ud834\udd1e", "c": false}"#;
+	let borrowed = parse_borrowed(input).unwrap();
+	let owned: JsonValue = input.parse().unwrap();
+
+	assert_eq!(borrowed.to_string().parse::<JsonValue>().unwrap(), owned);
+	assert_eq!(format!("{borrowed:?}").parse::<JsonValue>().unwrap(), owned);
+	assert_eq!(JsonValue::from(borrowed), owned);
+	assert!(parse_borrowed("[1, 2,]").is_err());
+}
+
+#[test]
+fn borrowed_massive_object() {
+	let input = "{\"ABSOLUTELY_MASSIVE_BOI\":".repeat(100000)
+		+ &"[".repeat(100000)
+		+ &"]".repeat(100000)
+		+ &"}".repeat(100000);
+	let json = parse_borrowed(&input).unwrap();
+
+	assert!(json.to_string() == input);
+	assert_eq!(json.into_owned(), input.parse::<JsonValue>().unwrap());
+}
+
+#[test]
+fn get_accessors() {
+	let mut json = json!({"name": "microjson", "tags": ["json", "tiny"], "version": 0.2, "stable": false, "extra": null});
+
+	assert_eq!(
+		json.get("name").and_then(JsonValue::as_str),
+		Some("microjson")
+	);
+	assert_eq!(
+		json.get("tags").and_then(|tags| tags.get(1)),
+		Some(&json!("tiny"))
+	);
+	let version = String::from("version");
+	assert_eq!(json.get(&version).and_then(JsonValue::as_f64), Some(0.2));
+	assert_eq!(json.get("stable").and_then(JsonValue::as_bool), Some(false));
+	assert_eq!(json.get("missing"), None);
+	assert_eq!(json.get(0), None);
+	assert_eq!(json["tags"].get("key"), None);
+	assert_eq!(json["tags"].get(2), None);
+	assert!(json["extra"].is_null());
+	assert!(json["tags"].is_list() && json.is_object() && !json["name"].is_number());
+	assert_eq!(json["name"].as_list(), None);
+	assert_eq!(json["version"].as_str(), None);
+
+	if let Some(tags) = json.get_mut("tags").and_then(JsonValue::as_list_mut) {
+		tags.push(json!("fast"));
+	}
+	*json.get_mut("stable").unwrap() = json!(true);
+	assert_eq!(json.get_mut("nope"), None);
+	assert_eq!(json["tags"].as_list().map(Vec::len), Some(3));
+	assert_eq!(json["stable"].as_bool(), Some(true));
+	assert_eq!(json.as_object().map(HashMap::len), Some(5));
+}
+
+#[test]
+fn at_returns_null() {
+	let json = json!({"a": [{"b": 1}]});
+	assert_eq!(json.at("a").at(0).at("b"), &json!(1));
+	assert!(json.at("a").at(5).at("b").is_null());
+	assert!(json.at("missing").at(0).is_null());
+	assert!(json.at(0).is_null());
+}
+
+#[test]
+fn type_errors() {
+	let json = json!({"list": [1, "two"], "flag": true});
+	assert_eq!(
+		f64::try_from(&json["list"][1]),
+		Err(TypeError::new(JsonKind::Number, JsonKind::String).into())
+	);
+	assert!(matches!(
+		<&Vec<_>>::try_from(&json["flag"]),
+		Err(Error::Type(TypeError {
+			found: JsonKind::Boolean,
+			..
+		}))
+	));
+	assert_eq!(
+		String::try_from(json!(null)),
+		Err(TypeError::new(JsonKind::String, JsonKind::Null).into())
+	);
+	assert!(matches!(
+		bool::try_from(JsonValueRef::List(vec![])),
+		Err(Error::Type(TypeError {
+			found: JsonKind::List,
+			..
+		}))
+	));
+
+	let err = json["list"].get_as::<&str>(0).unwrap_err();
+	assert_eq!(err.to_string(), "expected string, found number at /0");
+	assert_eq!(
+		json.get_as::<&HashMap<_, _>>("list"),
+		Err(TypeError::new(JsonKind::Object, JsonKind::List)
+			.with_path("/list")
+			.into())
+	);
+	assert_eq!(json.get_as::<&bool>("flag"), Ok(&true));
+	assert_eq!(
+		json.get_as::<f64>("a/b").map_err(|err| err.to_string()),
+		Err("expected number, found null at /a~1b".into())
+	);
+	assert_eq!(json.kind(), JsonKind::Object);
+	assert_eq!(json["list"][0].kind(), JsonKind::Number);
+}
+
+#[test]
+fn unified_error() {
+	use std::error::Error as _;
+
+	let err = "[1, }".parse::<JsonValue>().unwrap_err();
+	assert_eq!(err.to_string(), "unexpected character: } at byte 4");
+	assert!(err.source().is_none());
+
+	let err = JsonLines::new("1\n[\n".as_bytes())
+		.nth(1)
+		.unwrap()
+		.unwrap_err();
+	assert_eq!(err.offset(), Some(1));
+	assert!(err.to_string().starts_with("line 2: "));
+	assert!(err.source().is_some());
+
+	let err = Error::from(std::io::Error::other("disk on fire"));
+	assert_eq!(err.to_string(), "I/O error: disk on fire");
+	assert!(err.source().is_some());
+
+	assert!(FiniteF64::try_from(f64::NAN).is_err());
+	assert!(matches!(
+		FiniteF64::try_from(f64::INFINITY),
+		Err(Error::NumberRange(_))
+	));
+}
+
+#[test]
+fn json_macro_expressions() {
+	let name = "value";
+	let list = vec![json!(1), json!(2)];
+	let json = json!({
+		"negative": -93,
+		"math": -1.15 * 2.,
+		"call": name.len() as f64,
+		"nested": [-1, null, {"a": [true,],},],
+		"list": list,
+		[format!("{name}_key")]: name,
+	});
+	assert_eq!(
+		json,
+		"{\"negative\": -93, \"math\": -2.3, \"call\": 5, \"nested\": [-1, null, {\"a\": [true]}], \
+		 \"list\": [1, 2], \"value_key\": \"value\"}"
+			.parse::<JsonValue>()
+			.unwrap()
+	);
+	assert_eq!(json!([]), JsonValue::List(vec![]));
+	assert_eq!(json!({}), JsonValue::from(HashMap::new()));
+}
+
+#[test]
+fn json_macro_large_literals() {
+	let object = json!({
+		"k0": 0, "k1": 1, "k2": 2, "k3": 3, "k4": 4, "k5": 5, "k6": 6, "k7": 7,
+		"k8": 8, "k9": 9, "k10": 10, "k11": 11, "k12": 12, "k13": 13, "k14": 14, "k15": 15,
+		"k16": 16, "k17": 17, "k18": 18, "k19": 19, "k20": 20, "k21": 21, "k22": 22, "k23": 23,
+		"k24": 24, "k25": 25, "k26": 26, "k27": 27, "k28": 28, "k29": 29, "k30": 30, "k31": 31,
+		"k32": 32, "k33": 33, "k34": 34, "k35": 35, "k36": 36, "k37": 37, "k38": 38, "k39": 39,
+		"k40": 40, "k41": 41, "k42": 42, "k43": 43, "k44": 44, "k45": 45, "k46": 46, "k47": 47,
+		"k48": 48, "k49": 49, "k50": 50, "k51": 51, "k52": 52, "k53": 53, "k54": 54, "k55": 55,
+		"k56": 56, "k57": 57, "k58": 58, "k59": 59, "k60": 60, "k61": 61, "k62": 62, "k63": 63,
+		"k64": 64, "k65": 65, "k66": 66, "k67": 67, "k68": 68, "k69": 69, "k70": 70, "k71": 71,
+		"k72": 72, "k73": 73, "k74": 74, "k75": 75, "k76": 76, "k77": 77, "k78": 78, "k79": 79,
+		"k80": 80, "k81": 81, "k82": 82, "k83": 83, "k84": 84, "k85": 85, "k86": 86, "k87": 87,
+		"k88": 88, "k89": 89, "k90": 90, "k91": 91, "k92": 92, "k93": 93, "k94": 94, "k95": 95,
+		"k96": 96, "k97": 97, "k98": 98, "k99": 99,
+	});
+	let expected: HashMap<_, _> = (0..100)
+		.map(|idx| (format!("k{idx}"), json!(idx)))
+		.collect();
+	assert_eq!(object, JsonValue::from(expected));
+
+	let list = json!([
+		0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+		25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47,
+		48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70,
+		71, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93,
+		94, 95, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110, 111, 112,
+		113, 114, 115, 116, 117, 118, 119, 120, 121, 122, 123, 124, 125, 126, 127, 128, 129, 130,
+		131, 132, 133, 134, 135, 136, 137, 138, 139, 140, 141, 142, 143, 144, 145, 146, 147, 148,
+		149, 150, 151, 152, 153, 154, 155, 156, 157, 158, 159, 160, 161, 162, 163, 164, 165, 166,
+		167, 168, 169, 170, 171, 172, 173, 174, 175, 176, 177, 178, 179, 180, 181, 182, 183, 184,
+		185, 186, 187, 188, 189, 190, 191, 192, 193, 194, 195, 196, 197, 198, 199, 200, 201, 202,
+		203, 204, 205, 206, 207, 208, 209, 210, 211, 212, 213, 214, 215, 216, 217, 218, 219, 220,
+		221, 222, 223, 224, 225, 226, 227, 228, 229, 230, 231, 232, 233, 234, 235, 236, 237, 238,
+		239, 240, 241, 242, 243, 244, 245, 246, 247, 248, 249, 250, 251, 252, 253, 254, 255, 256,
+		257, 258, 259, 260, 261, 262, 263, 264, 265, 266, 267, 268, 269, 270, 271, 272, 273, 274,
+		275, 276, 277, 278, 279, 280, 281, 282, 283, 284, 285, 286, 287, 288, 289, 290, 291, 292,
+		293, 294, 295, 296, 297, 298, 299, 300, 301, 302, 303, 304, 305, 306, 307, 308, 309, 310,
+		311, 312, 313, 314, 315, 316, 317, 318, 319, 320, 321, 322, 323, 324, 325, 326, 327, 328,
+		329, 330, 331, 332, 333, 334, 335, 336, 337, 338, 339, 340, 341, 342, 343, 344, 345, 346,
+		347, 348, 349, 350, 351, 352, 353, 354, 355, 356, 357, 358, 359, 360, 361, 362, 363, 364,
+		365, 366, 367, 368, 369, 370, 371, 372, 373, 374, 375, 376, 377, 378, 379, 380, 381, 382,
+		383, 384, 385, 386, 387, 388, 389, 390, 391, 392, 393, 394, 395, 396, 397, 398, 399, 400,
+		401, 402, 403, 404, 405, 406, 407, 408, 409, 410, 411, 412, 413, 414, 415, 416, 417, 418,
+		419, 420, 421, 422, 423, 424, 425, 426, 427, 428, 429, 430, 431, 432, 433, 434, 435, 436,
+		437, 438, 439, 440, 441, 442, 443, 444, 445, 446, 447, 448, 449, 450, 451, 452, 453, 454,
+		455, 456, 457, 458, 459, 460, 461, 462, 463, 464, 465, 466, 467, 468, 469, 470, 471, 472,
+		473, 474, 475, 476, 477, 478, 479, 480, 481, 482, 483, 484, 485, 486, 487, 488, 489, 490,
+		491, 492, 493, 494, 495, 496, 497, 498, 499,
+	]);
+	assert_eq!(list, (0..500).collect::<JsonValue>());
+}
+
+#[test]
+fn json_macro_spread() {
+	let base = json!({"a": 1, "b": 2});
+	let json = json!({"a": 0, "c": 0, ..base.clone(), "b": 3});
+	assert_eq!(json, json!({"a": 1, "b": 3, "c": 0}));
+	assert_eq!(json!({ ..base.clone() }), base);
+	assert_eq!(
+		json!({"x": {..base, "d": 4},}),
+		json!({"x": {"a": 1, "b": 2, "d": 4}})
+	);
+	let map = HashMap::from([("e".to_string(), json!(5))]);
+	assert_eq!(json!({"d": 4, ..map}), json!({"d": 4, "e": 5}));
+}
+
+#[test]
+#[should_panic(expected = "json! can only spread objects, found list")]
+fn json_macro_spread_list() {
+	let list = json!([1]);
+	json!({ ..list });
+}
+
+mod hygiene {
+	#![no_implicit_prelude]
+
+	#[::core::prelude::v1::test]
+	fn json_macros_without_imports() {
+		let extra = crate::json!({"c": true});
+		let value = crate::json!({"a": [1, null, {"b": -2}], ["k"]: "v", ..extra});
+		::std::assert_eq!(
+			value,
+			crate::json_str!(r#"{"a": [1, null, {"b": -2}], "k": "v", "c": true}"#)
+		);
+	}
+}
+
+#[test]
+fn json_str_literal() {
+	let json = crate::json_str!(r#"{"a": [1, -2.5e3, "\u00e9\n"], "b": {"c": null}}"#);
+	assert_eq!(json, json!({"a": [1, -2500, "é\n"], "b": {"c": null}}));
+	assert_eq!(crate::json_str!("[]"), json!([]));
+}
+
+#[test]
+fn validate_literal_matches_parser() {
+	let inputs = [
+		"0",
+		"-0",
+		"01",
+		"-",
+		"1.",
+		"1.5.2",
+		"1e",
+		"1e+",
+		"1E-7",
+		"-a",
+		"tru",
+		"true",
+		"nul",
+		"null",
+		"\"abc",
+		"\"\\u00g0\"",
+		"\"\\ud83d\\ude00\"",
+		"\"\\x\"",
+		"\"tab\there\"",
+		"\"\x7f\"",
+		"[1,]",
+		"[,1]",
+		"[1 2]",
+		"[[]",
+		"[]]",
+		"{}",
+		"{\"a\"}",
+		"{\"a\" : 1 , }",
+		"{\"a\":1}x",
+		" 1",
+		"1 ",
+		"[ 1 , { \"a\" : [ ] } ]",
+		"{\"a\":{\"b\":[{}]}}",
+		"{1: 2}",
+		"",
+		"}",
+	];
+	for input in inputs {
+		assert_eq!(
+			crate::validate_literal(input).is_ok(),
+			input.parse::<JsonValue>().is_ok(),
+			"{input}"
+		);
+	}
+	assert!(crate::validate_literal(&"[".repeat(129)).is_err());
+}
+
+#[test]
+fn fluent_builders() {
+	let nickname: Option<&str> = None;
+	let json = JsonValue::object()
+		.with("name", "microjson")
+		.with_opt("nickname", nickname)
+		.with_opt("version", Some(0.2))
+		.with(
+			"tags",
+			JsonValue::list().push("json").push(true).extend([1, 2]),
+		)
+		.extend([("a", json!(null)), ("b", json!([]))]);
+	assert_eq!(
+		json,
+		json!({"name": "microjson", "version": 0.2, "tags": ["json", true, 1, 2], "a": null, "b": []})
+	);
+}
+
+#[test]
+fn from_iterator() {
+	let list: JsonValue = (1..=3).map(|num| num * 2).collect();
+	assert_eq!(list, json!([2, 4, 6]));
+
+	let object: JsonValue = ["x", "y"]
+		.iter()
+		.map(|&key| (key, key.len() as u32))
+		.collect();
+	assert_eq!(object, json!({"x": 1, "y": 1}));
+
+	let mut list = json!([]);
+	Extend::extend(&mut list, vec!["a", "b"]);
+	assert_eq!(list, json!(["a", "b"]));
+}
+
+#[test]
+#[should_panic]
+fn builder_wrong_kind() {
+	let _ = JsonValue::list().with("key", 1);
+}
+
+#[test]
+fn walk_pre_order() {
+	let json = json!({"a/b": [1, {"~c": null}]});
+	let walked: Vec<_> = json
+		.walk()
+		.map(|(pointer, value)| (pointer.to_string(), value.kind()))
+		.collect();
+	assert_eq!(
+		walked,
+		vec![
+			("".into(), JsonKind::Object),
+			("/a~1b".into(), JsonKind::List),
+			("/a~1b/0".into(), JsonKind::Number),
+			("/a~1b/1".into(), JsonKind::Object),
+			("/a~1b/1/~0c".into(), JsonKind::Null),
+		]
+	);
+
+	let pointer = JsonPointer::root().key("a/b").index(1);
+	assert_eq!(json.walk().nth(3).unwrap().0, pointer);
+	assert_eq!(pointer.depth(), 2);
+	assert_eq!(pointer.parent().unwrap().to_string(), "/a~1b");
+	assert!(pointer.parent().unwrap().parent().unwrap().is_root());
+}
+
+#[test]
+fn walk_mut_redact() {
+	let mut json =
+		json!({"user": {"password": "hunter2", "name": "x"}, "tokens": [{"password": 1}]});
+	let mut walk = json.walk_mut();
+	while let Some((pointer, value)) = walk.next() {
+		if let Some(obj) = value.as_object_mut()
+			&& let Some(password) = obj.get_mut("password")
+		{
+			*password = json!("***");
+		}
+		if pointer.to_string() == "/tokens/0" {
+			*value = json!({"replaced": {"password": 2}});
+		}
+	}
+	assert_eq!(
+		json,
+		json!({"user": {"password": "***", "name": "x"}, "tokens": [{"replaced": {"password": "***"}}]})
+	);
+
+	let mut seen = vec![];
+	let mut walk = json.walk_mut();
+	while let Some((pointer, _)) = walk.next() {
+		if pointer.depth() == 2 {
+			break;
+		}
+		seen.push(pointer.to_string());
+	}
+	assert_eq!(seen.len(), 2);
+}
+
+#[test]
+fn visitor_enter_leave() {
+	#[derive(Default)]
+	struct Depth {
+		current: usize,
+		max: usize,
+		leaves: Vec<String>,
+	}
+	impl Visitor for Depth {
+		fn enter(&mut self, pointer: &JsonPointer, value: &JsonValue) -> bool {
+			self.current += 1;
+			self.max = self.max.max(self.current);
+			!value.is_string() && pointer.to_string() != "/1/skip"
+		}
+
+		fn leave(&mut self, pointer: &JsonPointer, _: &JsonValue) {
+			self.current -= 1;
+			self.leaves.push(pointer.to_string());
+		}
+	}
+
+	let mut visitor = Depth::default();
+	json!([[["deep"]], {"skip": [[[]]]}]).visit(&mut visitor);
+	assert_eq!(visitor.current, 0);
+	assert_eq!(visitor.max, 4);
+	assert_eq!(
+		visitor.leaves,
+		["/0/0/0", "/0/0", "/0", "/1/skip", "/1", ""]
+	);
+}
+
+#[test]
+fn walk_massive() {
+	let mut json: JsonValue = ("[".repeat(100000) + &"]".repeat(100000)).parse().unwrap();
+	assert_eq!(json.walk().count(), 100000);
+	assert_eq!(json.walk().last().unwrap().0.depth(), 99999);
+
+	let mut count = 0;
+	let mut walk = json.walk_mut();
+	while walk.next().is_some() {
+		count += 1;
+	}
+	assert_eq!(count, 100000);
+
+	struct Counter(usize);
+	impl Visitor for Counter {
+		fn leave(&mut self, _: &JsonPointer, _: &JsonValue) {
+			self.0 += 1;
+		}
+	}
+	let mut counter = Counter(0);
+	json.visit(&mut counter);
+	assert_eq!(counter.0, 100000);
+}
+
+#[test]
+fn canonical_numbers() {
+	let vectors = [
+		(0x0000000000000000_u64, "0"),
+		(0x8000000000000000, "0"),
+		(0x0000000000000001, "5e-324"),
+		(0x8000000000000001, "-5e-324"),
+		(0x7fefffffffffffff, "1.7976931348623157e+308"),
+		(0xffefffffffffffff, "-1.7976931348623157e+308"),
+		(0x4340000000000000, "9007199254740992"),
+		(0xc340000000000000, "-9007199254740992"),
+		(0x4430000000000000, "295147905179352830000"),
+		(0x44b52d02c7e14af5, "9.999999999999997e+22"),
+		(0x44b52d02c7e14af6, "1e+23"),
+		(0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+		(0x444b1ae4d6e2ef4e, "999999999999999700000"),
+		(0x444b1ae4d6e2ef4f, "999999999999999900000"),
+		(0x444b1ae4d6e2ef50, "1e+21"),
+		(0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+		(0x3eb0c6f7a0b5ed8d, "0.000001"),
+		(0x41b3de4355555553, "333333333.3333332"),
+		(0x41b3de4355555554, "333333333.33333325"),
+		(0x41b3de4355555555, "333333333.3333333"),
+		(0x41b3de4355555556, "333333333.3333334"),
+		(0x41b3de4355555557, "333333333.33333343"),
+		(0xbecbf647612f3696, "-0.0000033333333333333333"),
+		(0x43143ff3c1cb0959, "1424953923781206.2"),
+	];
+	for (bits, expected) in vectors {
+		assert_eq!(
+			json!(f64::from_bits(bits)).to_canonical_string(),
+			expected,
+			"{bits:#x}"
+		);
+	}
+}
+
+#[test]
+#[allow(clippy::excessive_precision)]
+fn canonical_rfc_example() {
+	let json = json!({
+		"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+		"string": "\u{20ac}$\u{f}\nA'B\"\\\\\"/",
+		"literals": [null, true, false]
+	});
+	assert_eq!(
+		json.to_canonical_string(),
+		r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+	);
+}
+
+#[test]
+fn canonical_key_order() {
+	let json: JsonValue = r#"{
+		"€": "Euro Sign",
+		"\r": "Carriage Return",
+		"דּ": "Hebrew Letter Dalet With Dagesh",
+		"1": "One",
+		"😀": "Emoji: Grinning Face",
+		"\u0080": "Control",
+		"ö": "Latin Small Letter O With Diaeresis"
+	}"#
+	.parse()
+	.unwrap();
+	let values: Vec<_> = json
+		.to_canonical_string()
+		.split('"')
+		.skip(3)
+		.step_by(4)
+		.map(String::from)
+		.collect();
+	assert_eq!(
+		values,
+		[
+			"Carriage Return",
+			"One",
+			"Control",
+			"Latin Small Letter O With Diaeresis",
+			"Euro Sign",
+			"Emoji: Grinning Face",
+			"Hebrew Letter Dalet With Dagesh"
+		]
+	);
+	assert_eq!(
+		json!({"\u{7f}": [{}, []]}).to_canonical_string(),
+		"{\"\u{7f}\":[{},[]]}"
+	);
+}
+
+#[test]
+fn canonical_massive() {
+	let json: JsonValue = ("[{\"a\":".repeat(50000) + "1" + &"}]".repeat(50000))
+		.parse()
+		.unwrap();
+	assert_eq!(json.to_canonical_string().len(), 8 * 50000 + 1);
+}
+
+#[cfg(any(feature = "bson", feature = "cbor", feature = "msgpack"))]
+fn hex(s: &str) -> Vec<u8> {
+	(0..s.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+		.collect()
+}
+
+#[test]
+#[cfg(feature = "cbor")]
+fn cbor_rfc_vectors() {
+	use crate::cbor;
+
+	let vectors = [
+		(json!(0), "00"),
+		(json!(23), "17"),
+		(json!(24), "1818"),
+		(json!(1000), "1903e8"),
+		(json!(1000000), "1a000f4240"),
+		(json!(1000000000000_f64), "1b000000e8d4a51000"),
+		(json!(-1), "20"),
+		(json!(-1000), "3903e7"),
+		(json!(-0.), "f98000"),
+		(json!(1.1), "fb3ff199999999999a"),
+		(json!(1.5), "f93e00"),
+		(json!(3.4028234663852886e38), "fa7f7fffff"),
+		(json!(1.0e300), "fb7e37e43c8800759c"),
+		(json!(5.960464477539063e-8), "f90001"),
+		(json!(0.00006103515625), "f90400"),
+		(json!(-4.1), "fbc010666666666666"),
+		(json!(false), "f4"),
+		(json!(null), "f6"),
+		(json!(""), "60"),
+		(json!("\"\\"), "62225c"),
+		(json!("\u{fc}"), "62c3bc"),
+		(json!([1, [2, 3], [4, 5]]), "8301820203820405"),
+		(json!({"a": 1}), "a1616101"),
+		(json!(["a", {"b": "c"}]), "826161a161626163"),
+	];
+	for (value, encoded) in vectors {
+		assert_eq!(cbor::to_vec(&value), hex(encoded), "{value}");
+		assert_eq!(cbor::from_slice(&hex(encoded)), Ok(value));
+	}
+
+	let decoded = [
+		("1bffffffffffffffff", json!(18446744073709551615_u64 as f64)),
+		("3bffffffffffffffff", json!(-18446744073709551616_f64)),
+		("f97c00", json!(null)),
+		("fa7fc00000", json!(null)),
+		("f7", json!(null)),
+		(
+			"c074323031332d30332d32315432303a30343a30305a",
+			json!("2013-03-21T20:04:00Z"),
+		),
+		("7f657374726561646d696e67ff", json!("streaming")),
+		("9fff", json!([])),
+		("9f018202039f0405ffff", json!([1, [2, 3], [4, 5]])),
+		("bf61610161629f0203ffff", json!({"a": 1, "b": [2, 3]})),
+		("bf6346756ef563416d7421ff", json!({"Fun": true, "Amt": -2})),
+	];
+	for (encoded, value) in decoded {
+		assert_eq!(cbor::from_slice(&hex(encoded)), Ok(value), "{encoded}");
+	}
+}
+
+#[test]
+#[cfg(feature = "cbor")]
+fn cbor_errors() {
+	use crate::cbor;
+
+	let syntax = |encoded: &str| match cbor::from_slice(&hex(encoded)) {
+		Err(Error::Syntax { message, offset }) => (message, offset),
+		other => panic!("{encoded}: {other:?}"),
+	};
+	assert_eq!(syntax("a10101"), ("map keys must be strings".into(), 1));
+	assert_eq!(syntax("4161"), ("byte strings are not supported".into(), 1));
+	assert_eq!(syntax("8201"), ("unexpected end of input".into(), 2));
+	assert_eq!(syntax("0000"), ("unexpected trailing bytes".into(), 1));
+	assert_eq!(syntax("ff"), ("unexpected break".into(), 1));
+	assert_eq!(syntax("62c328"), ("invalid UTF-8".into(), 1));
+	assert_eq!(syntax("bf6161ff"), ("unexpected break".into(), 4));
+
+	let limits = ParseLimits {
+		max_depth: 2,
+		..Default::default()
+	};
+	assert!(cbor::from_slice_with(&hex("8180"), &limits).is_ok());
+	assert!(matches!(
+		cbor::from_slice_with(&hex("818180"), &limits),
+		Err(Error::LimitExceeded {
+			limit: Limit::Depth,
+			offset: 3
+		})
+	));
+}
+
+#[test]
+#[cfg(feature = "cbor")]
+fn cbor_round_trip() {
+	use crate::cbor;
+
+	// Each integer head width on both sides of its boundary, then the float widths: half,
+	// single, and double
+	let numbers = [
+		(23., 1),
+		(24., 2),
+		(255., 2),
+		(256., 3),
+		(65535., 3),
+		(65536., 5),
+		(4294967295., 5),
+		(4294967296., 9),
+		(18446744073709549568., 9),
+		(-24., 1),
+		(-25., 2),
+		(-256., 2),
+		(-257., 3),
+		(-65536., 3),
+		(-65537., 5),
+		(-4294967296., 5),
+		(-4294967297., 9),
+		(-18446744073709551616., 9),
+		(-0., 3),
+		(0.5, 3),
+		(65504.5, 5),
+		(5.960464477539063e-8, 3),
+		(18446744073709551616., 5),
+		(3.4028234663852886e38, 5),
+		(0.1, 9),
+		(1.7976931348623157e308, 9),
+	];
+	for (num, len) in numbers {
+		let value = JsonValue::from(num);
+		let encoded = cbor::to_vec(&value);
+		assert_eq!(encoded.len(), len, "{num}");
+		assert_eq!(cbor::from_slice(&encoded), Ok(value));
+	}
+
+	// Lengths use the same heads, for strings, lists and maps alike
+	for (len, head) in [(23, 1), (24, 2), (256, 3), (65536, 5)] {
+		let s = JsonValue::from("é".repeat(len / 2) + &"x".repeat(len % 2));
+		let list: JsonValue = (0..len).map(|_| JsonValue::Null).collect();
+		let obj: JsonValue = (0..len).map(|idx| (format!("{idx:05}"), 0)).collect();
+		assert_eq!(cbor::to_vec(&s).len(), head + len);
+		assert_eq!(cbor::to_vec(&list).len(), head + len);
+		assert_eq!(cbor::to_vec(&obj).len(), head + 7 * len);
+		for value in [s, list, obj] {
+			assert_eq!(cbor::from_slice(&cbor::to_vec(&value)), Ok(value));
+		}
+	}
+
+	// Negative integers past 2^53 are rounded once, the same way from_str rounds them
+	for (encoded, text) in [
+		("3b0020000000000001", "-9007199254740994"),
+		("3b0020000000000002", "-9007199254740995"),
+		("3b002329a731d6da49", "-9897422257314378"),
+		("3bffffffffffffffff", "-18446744073709551616"),
+	] {
+		assert_eq!(cbor::from_slice(&hex(encoded)), text.parse(), "{text}");
+	}
+
+	// Indefinite-length containers decode to the same value as definite ones
+	assert_eq!(
+		cbor::from_slice(&hex("bf61619f01f6ff6162a0ff")),
+		Ok(json!({"a": [1, null], "b": {}}))
+	);
+
+	let massive: JsonValue = ("{\"a\":".repeat(100000) + "[]" + &"}".repeat(100000))
+		.parse()
+		.unwrap();
+	let encoded = cbor::to_vec(&massive);
+	assert_eq!(encoded.len(), 3 * 100000 + 1);
+	assert_eq!(cbor::from_slice(&encoded), Ok(massive));
+}
+
+#[test]
+#[cfg(feature = "msgpack")]
+fn msgpack_encodings() {
+	use crate::msgpack;
+
+	let vectors = [
+		(json!(0), "00"),
+		(json!(127), "7f"),
+		(json!(128), "cc80"),
+		(json!(256), "cd0100"),
+		(json!(65536), "ce00010000"),
+		(json!(4294967296_f64), "cf0000000100000000"),
+		(json!(-1), "ff"),
+		(json!(-32), "e0"),
+		(json!(-33), "d0df"),
+		(json!(-129), "d1ff7f"),
+		(json!(-32769), "d2ffff7fff"),
+		(json!(-2147483649_f64), "d3ffffffff7fffffff"),
+		(json!(0.5), "ca3f000000"),
+		(json!(-0.), "ca80000000"),
+		(json!(1.1), "cb3ff199999999999a"),
+		(json!(null), "c0"),
+		(json!(true), "c3"),
+		(json!(""), "a0"),
+		(json!("é"), "a2c3a9"),
+		(json!("a".repeat(32)), &format!("d920{}", "61".repeat(32))),
+		(json!([1, [2]]), "92019102"),
+		(json!({"a": {}}), "81a16180"),
+		(
+			json!(vec![json!(null); 16]),
+			&format!("dc0010{}", "c0".repeat(16)),
+		),
+	];
+	for (value, encoded) in vectors {
+		assert_eq!(msgpack::to_vec(&value), Ok(hex(encoded)), "{value}");
+		assert_eq!(msgpack::from_slice(&hex(encoded)), Ok(value));
+	}
+
+	let decoded = [
+		("cb7ff0000000000000", json!(null)),
+		("d38000000000000000", json!(-9223372036854775808_f64)),
+		("cfffffffffffffffff", json!(18446744073709551615_u64 as f64)),
+		("dd00000001c2", json!([false])),
+		("df00000001a3616263c3", json!({"abc": true})),
+	];
+	for (encoded, value) in decoded {
+		assert_eq!(msgpack::from_slice(&hex(encoded)), Ok(value), "{encoded}");
+	}
+
+	let syntax = |encoded: &str| match msgpack::from_slice(&hex(encoded)) {
+		Err(Error::Syntax { message, offset }) => (message, offset),
+		other => panic!("{encoded}: {other:?}"),
+	};
+	assert_eq!(syntax("810101"), ("map keys must be strings".into(), 1));
+	assert_eq!(syntax("c40100"), ("binary data is not supported".into(), 1));
+	assert_eq!(syntax("c1"), ("invalid marker byte".into(), 1));
+	assert_eq!(syntax("92c0"), ("unexpected end of input".into(), 2));
+}
+
+#[test]
+#[cfg(feature = "msgpack")]
+fn msgpack_round_trip() {
+	use crate::msgpack;
+
+	// Integers pick the smallest format, and other numbers use float32 whenever it's exact
+	let numbers = [
+		(127., 0x7f),
+		(128., 0xcc),
+		(-32., 0xe0),
+		(-33., 0xd0),
+		(65535., 0xcd),
+		(4294967295., 0xce),
+		(4294967296., 0xcf),
+		(18446744073709549568., 0xcf),
+		(-2147483648., 0xd2),
+		(-9223372036854775808., 0xd3),
+		(-0., 0xca),
+		(0.5, 0xca),
+		(16777217.5, 0xcb),
+		(1.401298464324817e-45, 0xca),
+		(3.4028234663852886e38, 0xca),
+		(18446744073709551616., 0xca),
+		(-9223372036854777856., 0xcb),
+		(0.1, 0xcb),
+		(1e-7, 0xcb),
+	];
+	for (num, marker) in numbers {
+		let value = JsonValue::from(num);
+		let encoded = msgpack::to_vec(&value).unwrap();
+		assert_eq!(encoded[0], marker, "{num}");
+		assert_eq!(msgpack::from_slice(&encoded), Ok(value));
+	}
+
+	// fixstr, fixarray and fixmap up to their limits, then the 8, 16 and 32-bit forms
+	let strings = [
+		(31, 0xbf),
+		(32, 0xd9),
+		(255, 0xd9),
+		(256, 0xda),
+		(65536, 0xdb),
+	];
+	for (len, marker) in strings {
+		let value = JsonValue::from("x".repeat(len));
+		let encoded = msgpack::to_vec(&value).unwrap();
+		assert_eq!(encoded[0], marker, "{len}");
+		assert_eq!(msgpack::from_slice(&encoded), Ok(value));
+	}
+	let containers = [(15, 0x9f, 0x8f), (16, 0xdc, 0xde), (65536, 0xdd, 0xdf)];
+	for (len, list_marker, map_marker) in containers {
+		let list: JsonValue = (0..len).collect();
+		let obj: JsonValue = (0..len).map(|idx| (idx.to_string(), true)).collect();
+		for (value, marker) in [(list, list_marker), (obj, map_marker)] {
+			let encoded = msgpack::to_vec(&value).unwrap();
+			assert_eq!(encoded[0], marker, "{len}");
+			assert_eq!(msgpack::from_slice(&encoded), Ok(value));
+		}
+	}
+
+	let massive: JsonValue = ("[".repeat(100000) + &"]".repeat(100000)).parse().unwrap();
+	let encoded = msgpack::to_vec(&massive).unwrap();
+	assert_eq!(encoded.len(), 100000);
+	assert_eq!(msgpack::from_slice(&encoded), Ok(massive));
+
+	let limits = ParseLimits {
+		max_depth: 99999,
+		..Default::default()
+	};
+	assert!(matches!(
+		msgpack::from_slice_with(&encoded, &limits),
+		Err(Error::LimitExceeded {
+			limit: Limit::Depth,
+			..
+		})
+	));
+}
+
+#[test]
+#[cfg(feature = "bson")]
+fn bson_encodings() {
+	use crate::bson::{self, DecodeOptions};
+
+	// Wraps a single element in a document of the right size
+	let document = |element: &str| {
+		let size = (element.len() / 2 + 5) as u32;
+		[size.to_le_bytes().to_vec(), hex(element), vec![0]].concat()
+	};
+
+	let vectors = [
+		(json!({"a": 1}), "10610001000000"),
+		(json!({"a": -2147483648_f64}), "10610000000080"),
+		(json!({"a": 4294967296_f64}), "1261000000000001000000"),
+		(json!({"a": 0.5}), "016100000000000000e03f"),
+		(json!({"a": -0.}), "0161000000000000000080"),
+		(json!({"a": 1e300}), "0161009c7500883ce4377e"),
+		(json!({"a": "é"}), "02610003000000c3a900"),
+		(json!({"a": null}), "0a6100"),
+		(json!({"a": true}), "08610001"),
+		(json!({"a": {}}), "0361000500000000"),
+		(
+			json!({"a": [false, null]}),
+			"0461000c000000083000000a310000",
+		),
+	];
+	for (value, element) in vectors {
+		assert_eq!(bson::to_vec(&value), Ok(document(element)), "{value}");
+		assert_eq!(bson::from_slice(&document(element)), Ok(value));
+	}
+
+	let err = bson::to_vec(&json!([1])).unwrap_err();
+	assert_eq!(
+		err,
+		Error::Type(TypeError::new(JsonKind::Object, JsonKind::List).with_path(""))
+	);
+	assert_eq!(err.to_string(), "expected object, found list at the root");
+	assert!(matches!(
+		bson::to_vec(&json!({"a\0": 1})),
+		Err(Error::Encode(_))
+	));
+
+	let extended = DecodeOptions {
+		extended_json: true,
+		..Default::default()
+	};
+	let decoded = [
+		(
+			"07610000112233445566778899aabb",
+			json!({"$oid": "00112233445566778899aabb"}),
+		),
+		(
+			"0961007b68e5cf8b010000",
+			json!({"$date": "2023-11-14T22:13:20.123Z"}),
+		),
+		(
+			"096100ffffffffffffffff",
+			json!({"$date": {"$numberLong": "-1"}}),
+		),
+		(
+			"05610004000000806869213f",
+			json!({"$binary": {"base64": "aGkhPw==", "subType": "80"}}),
+		),
+		(
+			"0b6100612a006900",
+			json!({"$regularExpression": {"pattern": "a*", "options": "i"}}),
+		),
+		(
+			"1161000200000001000000",
+			json!({"$timestamp": {"t": 1, "i": 2}}),
+		),
+		(
+			"1261000100000000002000",
+			json!({"$numberLong": "9007199254740993"}),
+		),
+		(
+			"1361007b000000000000000000000000003c30",
+			json!({"$numberDecimal": "1.23"}),
+		),
+		(
+			"1361007b000000000000000000000000004630",
+			json!({"$numberDecimal": "1.23E+5"}),
+		),
+		(
+			"016100000000000000f07f",
+			json!({"$numberDouble": "Infinity"}),
+		),
+		("ff6100", json!({"$minKey": 1})),
+		("066100", json!({"$undefined": true})),
+		("0d6100020000007800", json!({"$code": "x"})),
+		(
+			"0f6100160000000200000078000c000000106e000100000000",
+			json!({"$code": "x", "$scope": {"n": 1}}),
+		),
+	];
+	for (element, value) in decoded {
+		let decoded = bson::from_slice_with(&document(element), &extended);
+		assert_eq!(decoded, Ok(json!({"a": value})), "{element}");
+	}
+
+	let syntax = |bytes: &[u8]| match bson::from_slice(bytes) {
+		Err(Error::Syntax { message, offset }) => (message, offset),
+		other => panic!("{bytes:?}: {other:?}"),
+	};
+	assert_eq!(
+		syntax(&document("07610000112233445566778899aabb")),
+		("BSON type 0x07 has no JSON equivalent".into(), 4)
+	);
+	assert_eq!(
+		syntax(&document("206100")),
+		("unknown BSON type 0x20".into(), 4)
+	);
+	assert_eq!(
+		syntax(&hex("0400000000")),
+		("invalid document size".into(), 4)
+	);
+	assert_eq!(
+		syntax(&hex("050000000000")),
+		("unexpected trailing bytes".into(), 5)
+	);
+}
+
+#[test]
+#[cfg(feature = "bson")]
+fn bson_round_trip() {
+	use crate::bson;
+
+	// The element type of a number in a one-field document
+	let element_type = |num: f64| bson::to_vec(&json!({"n": num})).unwrap()[4];
+	let numbers = [
+		(2147483647., 0x10),
+		(-2147483648., 0x10),
+		(2147483648., 0x12),
+		(-2147483649., 0x12),
+		(9007199254740993., 0x12),
+		(-9223372036854775808., 0x12),
+		(9223372036854775808., 0x01),
+		(-0., 0x01),
+		(0.5, 0x01),
+		(1e300, 0x01),
+	];
+	for (num, expected) in numbers {
+		assert_eq!(element_type(num), expected, "{num}");
+		let value = json!({"n": num});
+		assert_eq!(bson::from_slice(&bson::to_vec(&value).unwrap()), Ok(value));
+	}
+
+	// Lists become documents keyed "0", "1", ..., and come back as lists, including an empty
+	// one and one with more than ten elements, where the keys stop sorting numerically
+	let list: JsonValue = (0..12).map(|_| json!([])).collect();
+	let value = json!({"empty": [], "list": list, "é": {"": ""}});
+	let encoded = bson::to_vec(&value).unwrap();
+	assert!(encoded.windows(4).any(|window| window == b"\x0411\0"));
+	assert_eq!(bson::from_slice(&encoded), Ok(value));
+
+	// Nested documents each carry their own size, so this also checks every size is patched
+	let massive: JsonValue = ("[".repeat(100000) + &"]".repeat(100000)).parse().unwrap();
+	let massive = json!({"a": massive});
+	let encoded = bson::to_vec(&massive).unwrap();
+	assert_eq!(encoded.len(), 5 + 8 * 100000);
+	assert_eq!(bson::from_slice(&encoded), Ok(massive));
+}
+
+#[test]
+fn binary_round_trip() {
+	// Lengths are LEB128, so these cross from one to two to three length bytes. The string
+	// follows the header, the empty key table and its tag.
+	for (len, len_bytes) in [(127, 1), (128, 2), (16383, 2), (16384, 3)] {
+		let s = JsonValue::from("x".repeat(len));
+		let list: JsonValue = (0..len).map(|_| JsonValue::Null).collect();
+		let encoded = s.to_binary();
+		assert_eq!(encoded.len(), 6 + 1 + 1 + len_bytes + len);
+		assert_eq!(JsonValue::from_binary(&encoded), Ok(s));
+		assert_eq!(JsonValue::from_binary(&list.to_binary()), Ok(list));
+	}
+
+	// Numbers are stored as raw bits, so the sign of zero and subnormals survive
+	for num in [-0., 5e-324, f64::MIN_POSITIVE, -1.7976931348623157e308] {
+		let bytes = JsonValue::from(num).to_binary();
+		let decoded = JsonValue::from_binary(&bytes).unwrap().as_f64().unwrap();
+		assert_eq!(decoded.to_bits(), num.to_bits());
+	}
+
+	// Keys repeated across records and levels are stored once. A string value equal to a key
+	// is still stored inline.
+	let records: JsonValue = (0..100)
+		.map(|idx| json!({"identifier": idx, "": "identifier", "child": {"identifier": null}}))
+		.collect();
+	let deduped = records.to_binary();
+	let inline = records.to_binary_with(&BinaryOptions { dedup_keys: false });
+	assert_eq!(&deduped[..6], b"MJSN\x01\x01");
+	assert_eq!(&inline[..6], b"MJSN\x01\x00");
+	assert_eq!(deduped[6], 3);
+	// Inline, a record spends 29 bytes on its keys. Deduped, it spends four one-byte indexes,
+	// and the 19-byte table is written once.
+	assert_eq!(inline.len() - deduped.len(), 100 * (29 - 4) - 19);
+	assert_eq!(JsonValue::from_binary(&deduped).as_ref(), Ok(&records));
+	assert_eq!(JsonValue::from_binary(&inline), Ok(records));
+
+	let massive: JsonValue = ("[".repeat(100000) + &"]".repeat(100000)).parse().unwrap();
+	assert_eq!(JsonValue::from_binary(&massive.to_binary()), Ok(massive));
+}
+
+#[test]
+fn binary_errors() {
+	let syntax = |bytes: &[u8]| match JsonValue::from_binary(bytes) {
+		Err(Error::Syntax { message, offset }) => (message, offset),
+		other => panic!("{bytes:?}: {other:?}"),
+	};
+	assert_eq!(syntax(b"{}"), ("not a microjson binary snapshot".into(), 0));
+	assert_eq!(
+		syntax(b"MJSN\x02\x00\x00"),
+		("unsupported binary format version 2".into(), 4)
+	);
+	assert_eq!(
+		syntax(b"MJSN\x01\x00\x05\x02\x00"),
+		("unexpected end of input".into(), 9)
+	);
+	assert_eq!(syntax(b"MJSN\x01\x00\x07"), ("invalid tag".into(), 6));
+	assert_eq!(
+		syntax(b"MJSN\x01\x01\x00\x06\x01\x00\x00"),
+		("invalid key index".into(), 9)
+	);
+	assert_eq!(
+		syntax(b"MJSN\x01\x00\x00\x00"),
+		("unexpected trailing bytes".into(), 7)
+	);
+
+	let nan = [b"MJSN\x01\x00\x03".as_slice(), &f64::NAN.to_le_bytes()].concat();
+	assert_eq!(syntax(&nan), ("invalid number".into(), 7));
+}
+
+#[test]
+fn indexed_binary_view() {
+	let json = json!({
+		"users": [
+			{"name": "ada", "admin": true, "score": 1.5},
+			{"name": "bob", "admin": false, "score": null},
+		],
+		"": "empty key",
+		"nested": {"b": {"c": [1, [2, 3]]}, "a": "é"},
+	});
+	let bytes = json.to_indexed_binary();
+	let view = JsonView::new(&bytes).unwrap();
+	assert!(view.is_object());
+	assert_eq!(view.len(), 3);
+	assert_eq!(view.to_value(), json);
+
+	let users = view.get("users").unwrap();
+	assert!(users.is_list());
+	assert_eq!(
+		users.index(0).and_then(|user| user.get("name")?.as_str()),
+		Some("ada")
+	);
+	assert_eq!(
+		users.index(1).and_then(|user| user.get("admin")?.as_bool()),
+		Some(false)
+	);
+	assert_eq!(
+		users.index(0).and_then(|user| user.get("score")?.as_f64()),
+		Some(1.5)
+	);
+	assert!(
+		users
+			.index(1)
+			.and_then(|user| user.get("score"))
+			.unwrap()
+			.is_null()
+	);
+	assert!(users.index(2).is_none());
+	assert!(users.get("name").is_none());
+	assert!(view.get("missing").is_none());
+	assert_eq!(view.get("").and_then(|val| val.as_str()), Some("empty key"));
+
+	let nested = view.get("nested").unwrap();
+	let keys: Vec<_> = nested.entries().map(|(key, _)| key).collect();
+	assert_eq!(keys, ["a", "b"]);
+	let list = nested.get("b").and_then(|b| b.get("c")).unwrap();
+	let numbers: Vec<_> = list.iter().map(|val| val.kind()).collect();
+	assert_eq!(numbers, [JsonKind::Number, JsonKind::List]);
+	assert_eq!(list.index(1).unwrap().to_value(), json!([2, 3]));
+	assert_eq!(list.iter().count(), 2);
+	assert_eq!(list.index(0).unwrap().iter().count(), 0);
+
+	let massive: JsonValue = ("[".repeat(100000) + &"]".repeat(100000)).parse().unwrap();
+	let bytes = massive.to_indexed_binary();
+	assert_eq!(JsonView::new(&bytes).unwrap().to_value(), massive);
+
+	assert!(matches!(
+		JsonView::new(b"MJSN\x01\x00"),
+		Err(Error::Syntax { offset: 0, .. })
+	));
+	assert!(matches!(
+		JsonView::new(b"MJSI\x02\x00"),
+		Err(Error::Syntax { offset: 4, .. })
+	));
+
+	// Offsets that point outside the buffer read as missing
+	let mut bytes = json!([[1]]).to_indexed_binary();
+	bytes[14..22].copy_from_slice(&u64::MAX.to_le_bytes());
+	let view = JsonView::new(&bytes).unwrap();
+	assert_eq!(view.len(), 1);
+	assert!(view.index(0).is_none());
+	assert_eq!(view.to_value(), json!([]));
+
+	// So do offsets that point back up the tree, instead of looping forever
+	let mut bytes = json!([[1], 2]).to_indexed_binary();
+	bytes[22..30].copy_from_slice(&5_u64.to_le_bytes());
+	assert_eq!(JsonView::new(&bytes).unwrap().to_value(), json!([[1]]));
+}
+
+#[test]
+fn fast_parser_matches_from_str() {
+	let mut inputs: Vec<String> = [
+		r#"{"a": [1, -2.5, 1e3, 0, -0], "b": {"c": null, "d": [true, false]}, "e": ""}"#,
+		r#"["\"", "\\", "\\\"", "a\\\\\"b", "\u00e9\ud83d\ude00", "\/\b\f\n\r\t", "é"]"#,
+		"[]",
+		"{}",
+		"[[[[]]], {}, [{}]]",
+		"\"top\"",
+		"-12",
+		"true",
+		"",
+		" 1",
+		"1 ",
+		"[1,]",
+		"[,1]",
+		"{\"a\" 1}",
+		"{\"a\": 1,}",
+		"{1: 2}",
+		"[\"a\nb\"]",
+		"[\"a\u{7f}b\"]",
+		"[\"abc",
+		"[\"abc\\",
+		"[truex]",
+		"[nul]",
+		"[1 2]",
+		"[1]]",
+		"[1}",
+		"[\"a\"\"b\"]",
+		"[\"\\q\"]",
+		"[\"\\u12\"]",
+		"[01]",
+		"[-]",
+		"[1x]",
+		"{\"a\": 1} x",
+		"[\\\"a\"]",
+	]
+	.map(String::from)
+	.into();
+
+	// Runs of backslashes, quotes and control characters around the 64-byte block boundaries
+	for pad in 50..80 {
+		for slashes in 0..4 {
+			let s = "\\".repeat(slashes);
+			inputs.push(format!("[{}\"{s}{s}\" , \"x\"]", " ".repeat(pad)));
+			inputs.push(format!("[\"{}{s}\"]", "a".repeat(pad)));
+			inputs.push(format!("[\"{}{s}\n\"]", "a".repeat(pad)));
+			inputs.push(format!(
+				"{{\"{}\": [1, 2{}]}}",
+				"k".repeat(pad),
+				" ".repeat(slashes * 20)
+			));
+			inputs.push(format!("[{}tru{}]", " ".repeat(pad), "e".repeat(slashes)));
+		}
+	}
+
+	for input in &inputs {
+		let expected = input.parse::<JsonValue>();
+		assert_eq!(JsonValue::parse_fast(input), expected, "{input:?}");
+		let swar = crate::fast::parse(
+			input,
+			&ParseOptions::default(),
+			crate::fast::classify_swar,
+			&mut (),
+		);
+		assert_eq!(swar.map_err(Error::from), expected, "{input:?}");
+	}
+
+	let options = ParseOptions {
+		limits: ParseLimits {
+			max_depth: 2,
+			max_string_len: 3,
+			max_entries: 2,
+			..Default::default()
+		},
+		duplicate_keys: DuplicateKeys::CollectIntoList,
+	};
+	let inputs = [
+		r#"{"a": 1, "a": 2, "b": "abc"}"#,
+		r#"[[[]]]"#,
+		r#"["abcd"]"#,
+		r#"[1, 2, 3]"#,
+		r#"{"abcd": 1}"#,
+	];
+	for input in inputs {
+		let fast = JsonValue::parse_fast_with(input, &options);
+		assert_eq!(fast, JsonValue::parse_with(input, &options), "{input:?}");
+	}
+}
+
+#[test]
+fn fast_parser_numbers() {
+	let num = |input: &str| JsonValue::parse_fast(input).map(|val| val.as_f64().unwrap());
+	assert_eq!(num("1.7976931348623157e308"), Ok(f64::MAX));
+	assert_eq!(num("0.1"), Ok(0.1));
+	assert_eq!(num("-0").map(f64::is_sign_negative), Ok(true));
+	assert_eq!(
+		num("123456789012345678901234567890"),
+		Ok(1.2345678901234568e29)
+	);
+
+	let syntax = |input: &str| match JsonValue::parse_fast(input) {
+		Err(Error::Syntax { message, offset }) => (message, offset),
+		other => panic!("{input}: {other:?}"),
+	};
+	assert_eq!(syntax("1."), ("unexpected end of input".into(), 2));
+	assert_eq!(syntax("[1.e5]"), ("unexpected character: e".into(), 3));
+	assert_eq!(syntax("1e+"), ("unexpected end of input".into(), 3));
+	assert_eq!(syntax("-01"), ("illegal leading zero".into(), 1));
+}
+
+#[test]
+fn fast_parser_errors_match_from_str() {
+	let inputs = [
+		"é",
+		"[é]",
+		"{\"a\": é}",
+		"[1, é]",
+		"[1é]",
+		"[1 é]",
+		"[1] é",
+		"{\"a\"é}",
+		"trué",
+		"[nulé]",
+		"[truex]",
+		"[-x]",
+		"[-]",
+		"[01]",
+		"[1 2]",
+		"[1,]",
+		"[,1]",
+		"[1}",
+		"{\"a\":1]",
+		"[1,2]]",
+		"{}}",
+		"{1:2}",
+		"{\"a\" 1}",
+		"{\"a\":1,}",
+		"{\"a\":1 \"b\":2}",
+		"[\"a\" \"b\"]",
+		"[tru]",
+		"[\"\\x\"]",
+		"[\"a]",
+		"[\"\u{1}\"]",
+		" 1",
+		"[",
+		"{\"a\":",
+		"[1,",
+		"1x",
+		"-",
+	];
+	for input in inputs {
+		let expected = input.parse::<JsonValue>();
+		assert!(expected.is_err(), "{input}");
+		assert_eq!(JsonValue::parse_fast(input), expected, "{input}");
+	}
+	assert_eq!(
+		JsonValue::parse_fast("[1, é]").map_err(|err| err.to_string()),
+		Err("unexpected character: é at byte 4".into())
+	);
+}
+
+#[test]
+fn fast_parser_classifiers_agree() {
+	use crate::fast::{classifier, classify_swar, index};
+
+	let mut block = [0; 64];
+	for start in (0..=255_u8).step_by(64) {
+		for (idx, byte) in block.iter_mut().enumerate() {
+			*byte = start + idx as u8;
+		}
+		assert_eq!(classifier()(&block), classify_swar(&block));
+	}
+
+	let input = format!(
+		"{{\"a\\\\\\\"\": [{}\"\\u0001\", 12, true]}}",
+		" \t\r\n".repeat(40)
+	);
+	let expected = index(input.as_bytes(), classify_swar);
+	let actual = index(input.as_bytes(), classifier());
+	assert_eq!(actual.structurals, expected.structurals);
+	assert_eq!(actual.controls, expected.controls);
+}
+
+#[test]
+fn arena_read_api() {
+	let mut arena = JsonArena::new();
+	let input =
+		r#"{"name": "arena", "tags": ["a", "b\u0063"], "n": 2.5, "ok": true, "none": null}"#;
+	let json = arena.parse(input).unwrap();
+
+	assert_eq!(json.kind(), JsonKind::Object);
+	assert_eq!(json.get("name").and_then(|val| val.as_str()), Some("arena"));
+	assert_eq!(json.at("tags").at(1).as_str(), Some("bc"));
+	assert_eq!(json.at("n").as_f64(), Some(2.5));
+	assert_eq!(json.at("ok").as_bool(), Some(true));
+	assert!(json.at("none").is_null() && json.get("none").is_some());
+	assert!(json.get("missing").is_none() && json.at("tags").get(2).is_none());
+	assert!(json.get(0).is_none() && json.at("tags").get("a").is_none());
+
+	let tags = json.at("tags").as_list().unwrap();
+	assert_eq!(tags.len(), 2);
+	assert_eq!(
+		tags.iter()
+			.filter_map(|val| val.as_str())
+			.collect::<Vec<_>>(),
+		["a", "bc"]
+	);
+	let obj = json.as_object().unwrap();
+	assert_eq!(
+		obj.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+		["n", "name", "none", "ok", "tags"]
+	);
+	assert!(matches!(obj.get("ok"), Some(ArenaValue::Boolean(true))));
+	assert_eq!(json.into_owned(), input.parse::<JsonValue>().unwrap());
+	assert_eq!(json.to_string().parse::<JsonValue>(), input.parse());
+}
+
+#[test]
+fn arena_matches_from_str() {
+	let inputs = [
+		r#"{"a": [1, -2.5, 1e3, {"b": [[], {}]}], "c": "\ud83d\ude00", "d": 1e999}"#,
+		"[[[[]]], {}, [{}]]",
+		"\"top\"",
+		"",
+		" 1",
+		"[1,]",
+		"{\"a\": 1,}",
+		"{\"a\": [1, 2}",
+		"[01]",
+		"{\"a\": 1} x",
+	];
+	let mut arena = JsonArena::new();
+	for input in inputs {
+		let expected = input.parse::<JsonValue>();
+		let actual = arena.parse(input).map(ArenaValue::into_owned);
+		assert_eq!(actual, expected, "{input:?}");
+	}
+
+	let input = "[".repeat(100000) + &"]".repeat(100000);
+	let json = arena.parse(&input).unwrap();
+	assert_eq!(json.into_owned(), input.parse::<JsonValue>().unwrap());
+}
+
+#[test]
+fn arena_duplicate_keys_and_limits() {
+	let input = r#"{"b": 1, "a": [2], "c": {"a": 4, "a": 5}, "b": 3, "a": 6, "b": 7}"#;
+	let mut arena = JsonArena::new();
+	for duplicate_keys in [
+		DuplicateKeys::Error,
+		DuplicateKeys::FirstWins,
+		DuplicateKeys::LastWins,
+		DuplicateKeys::CollectIntoList,
+	] {
+		let options = ParseOptions {
+			duplicate_keys,
+			..Default::default()
+		};
+		let actual = arena
+			.parse_with(input, &options)
+			.map(ArenaValue::into_owned);
+		assert_eq!(actual, JsonValue::parse_with(input, &options));
+	}
+
+	let options = ParseOptions {
+		limits: ParseLimits {
+			max_depth: 2,
+			max_string_len: 3,
+			max_entries: 2,
+			..Default::default()
+		},
+		..Default::default()
+	};
+	for input in [
+		r#"[[[]]]"#,
+		r#"["abcd"]"#,
+		r#"[1, 2, 3]"#,
+		r#"{"a": [1, 2]}"#,
+	] {
+		let actual = arena
+			.parse_with(input, &options)
+			.map(ArenaValue::into_owned);
+		assert_eq!(actual, JsonValue::parse_with(input, &options), "{input:?}");
+	}
+}
+
+#[test]
+fn interned_keys_are_shared() {
+	let mut interner = KeyInterner::new();
+	let records = interner
+		.parse(r#"[{"id": 1, "tags": {"id": "x"}}, {"id": 2, "tags": {}}]"#)
+		.unwrap();
+	let more = interner.parse(r#"{"id": 3}"#).unwrap();
+	assert_eq!(interner.len(), 2);
+
+	let key = |value: &InternedValue| match value {
+		InternedValue::Object(obj) => obj.keys().find(|key| &***key == "id").unwrap().clone(),
+		_ => panic!("expected an object"),
+	};
+	let first = key(&records[0]);
+	assert!(std::sync::Arc::ptr_eq(&first, &key(&records[1])));
+	assert!(std::sync::Arc::ptr_eq(&first, &key(&records[0]["tags"])));
+	assert!(std::sync::Arc::ptr_eq(&first, &key(&more)));
+	assert_eq!(records[0]["tags"]["id"].kind(), JsonKind::String);
+}
+
+#[test]
+fn interned_accessors() {
+	let mut value = KeyInterner::new()
+		.parse(r#"{"name": "a", "tags": [true, 2], "meta": {}}"#)
+		.unwrap();
+	assert_eq!(value["name"].as_str(), Some("a"));
+	assert_eq!(
+		value
+			.get("tags")
+			.and_then(|tags| tags.get(1))
+			.and_then(InternedValue::as_f64),
+		Some(2.)
+	);
+	assert!(value.at("missing").at(0).is_null() && value["meta"].is_object());
+	assert_eq!(value["tags"].as_list().map(Vec::len), Some(2));
+	assert_eq!(<&bool>::try_from(&value["tags"][0]), Ok(&true));
+	assert!(String::try_from(InternedValue::Boolean(true)).is_err());
+
+	*value.get_mut("name").unwrap() = InternedValue::Boolean(false);
+	value["meta"]["new"] = InternedValue::Null;
+	assert!(value.as_list_mut().is_none());
+	assert_eq!(
+		JsonValue::from(value),
+		json!({"name": false, "tags": [true, 2], "meta": {"new": null}})
+	);
+}
+
+#[test]
+fn interned_matches_from_str() {
+	let mut interner = KeyInterner::new();
+	for input in [
+		r#"{"a": [1, -2.5, {"b": null}], "c": "😀", "d": true}"#,
+		"[]",
+		"[1,]",
+		"{\"a\": 1} x",
+	] {
+		let expected = input.parse::<JsonValue>();
+		assert_eq!(interner.parse(input).map(JsonValue::from), expected);
+	}
+
+	let input = r#"{"b": 1, "a": [2], "b": 3, "c": {"a": 4}, "a": 5, "b": 6}"#;
+	for duplicate_keys in [
+		DuplicateKeys::Error,
+		DuplicateKeys::FirstWins,
+		DuplicateKeys::LastWins,
+		DuplicateKeys::CollectIntoList,
+	] {
+		let options = ParseOptions {
+			duplicate_keys,
+			..Default::default()
+		};
+		let actual = interner.parse_with(input, &options).map(JsonValue::from);
+		assert_eq!(actual, JsonValue::parse_with(input, &options));
+	}
+
+	let input = "{\"a\":".repeat(100000) + "[]" + &"}".repeat(100000);
+	let json = interner.parse(&input).unwrap();
+	assert_eq!(json.to_string(), input);
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn value_layout() {
+	use std::mem::size_of;
+	assert_eq!(size_of::<JsonValue>(), 32);
+	assert_eq!(size_of::<Option<JsonValue>>(), 32);
+	assert_eq!(size_of::<JsonString>(), 24);
+	assert_eq!(size_of::<JsonObject>(), 8);
+	assert_eq!(size_of::<JsonValueRef>(), 56);
+	assert_eq!(size_of::<InternedValue>(), 32);
+	assert_eq!(size_of::<ArenaValue>(), 32);
+	assert_eq!(size_of::<FiniteF64>(), 8);
+
+	// Strings of up to 22 bytes are stored inline
+	for (s, inline) in [
+		("", true),
+		("日本語日本語日本", false),
+		("a".repeat(22).as_str(), true),
+	] {
+		let json = JsonValue::from(s);
+		let JsonValue::String(stored) = &json else {
+			panic!("expected a string");
+		};
+		assert_eq!((stored.as_str(), stored.is_inline()), (s, inline));
+	}
+	let input = format!(
+		r#"["{}", "{}", "\u65e5{}"]"#,
+		"b".repeat(22),
+		"c".repeat(23),
+		"d".repeat(19)
+	);
+	let json: JsonValue = input.parse().unwrap();
+	let inline: Vec<_> = (0..3)
+		.map(|idx| matches!(&json[idx], JsonValue::String(s) if s.is_inline()))
+		.collect();
+	assert_eq!(inline, [true, false, true]);
+	assert_eq!(
+		json[2].as_str(),
+		Some(format!("日{}", "d".repeat(19)).as_str())
+	);
+	assert_eq!(
+		String::try_from(json!("c".repeat(23))).unwrap(),
+		"c".repeat(23)
+	);
+	assert_eq!(<&str>::try_from(&json[0]), Ok("b".repeat(22).as_str()));
+
+	// Empty lists and objects don't allocate, and objects don't even build a HashMap until
+	// something is inserted
+	for json in [
+		r#"{"list": [], "object": {}}"#.parse::<JsonValue>().unwrap(),
+		JsonValue::parse_fast(r#"{"list": [], "object": {}}"#).unwrap(),
+		json!({"list": [], "object": {}}),
+	] {
+		assert_eq!(json["list"].as_list().unwrap().capacity(), 0);
+		assert!(matches!(
+			&json["object"],
+			JsonValue::Object(JsonObject(None))
+		));
+		assert!(json["object"].as_object().unwrap().is_empty());
+	}
+	let mut json = JsonValue::object();
+	assert!(matches!(json, JsonValue::Object(JsonObject(None))));
+	json["key"] = JsonValue::Null;
+	assert_eq!(json.as_object().unwrap().len(), 1);
+}
+
+#[test]
+fn shared_updates_copy_only_their_path() {
+	use std::sync::Arc;
+
+	let original: SharedJson =
+		r#"{"db": {"hosts": ["a", "b"], "port": 5432}, "cache": {"ttl": 60}}"#
+			.parse()
+			.unwrap();
+	let mut updated = original.clone();
+	updated["db"]["hosts"][1] = SharedJson::String("c".into());
+	updated["db"]["user"] = SharedJson::Boolean(true);
+	*updated.get_mut("cache").unwrap().get_mut("ttl").unwrap() = SharedJson::Null;
+	updated.as_object_mut().unwrap().remove("cache");
+
+	assert_eq!(
+		original.to_value(),
+		json!({"db": {"hosts": ["a", "b"], "port": 5432}, "cache": {"ttl": 60}})
+	);
+	assert_eq!(
+		updated.to_value(),
+		json!({"db": {"hosts": ["a", "c"], "port": 5432, "user": true}})
+	);
+
+	let shared = |lhs: &SharedJson, rhs: &SharedJson| match (lhs, rhs) {
+		(SharedJson::List(l), SharedJson::List(r)) => Arc::ptr_eq(l, r),
+		(SharedJson::Object(l), SharedJson::Object(r)) => Arc::ptr_eq(l, r),
+		(SharedJson::String(l), SharedJson::String(r)) => Arc::ptr_eq(l, r),
+		_ => false,
+	};
+	assert!(!shared(&original["db"], &updated["db"]));
+	assert!(shared(
+		&original["db"]["hosts"][0],
+		&updated["db"]["hosts"][0]
+	));
+	let copy = updated.clone();
+	assert!(shared(&copy, &updated) && shared(&copy["db"], &updated["db"]));
+	assert!(original.at("missing").at(3).is_null());
+}
+
+#[test]
+fn value_types_index_alike() {
+	fn message(index: impl FnOnce() + std::panic::UnwindSafe) -> String {
+		let err = std::panic::catch_unwind(index).unwrap_err();
+		match err.downcast::<String>() {
+			Ok(msg) => *msg,
+			Err(err) => err.downcast_ref::<&str>().unwrap().to_string(),
+		}
+	}
+
+	const INPUT: &str = r#"{"list": [1], "name": "a"}"#;
+	let cases: [fn(&str) -> String; 4] = [
+		|key| message(|| _ = &INPUT.parse::<JsonValue>().unwrap()[key][1]),
+		|key| message(|| _ = &INPUT.parse::<SharedJson>().unwrap()[key][1]),
+		|key| message(|| _ = &parse_borrowed(INPUT).unwrap()[key][1]),
+		|key| message(|| _ = &KeyInterner::new().parse(INPUT).unwrap()[key][1]),
+	];
+	for key in ["list", "name", "missing"] {
+		let expected = cases[0](key);
+		for case in &cases[1..] {
+			assert_eq!(case(key), expected);
+		}
+	}
+	assert!(cases[0]("name").contains("expected: List, found: String"));
+
+	let mut json: SharedJson = INPUT.parse().unwrap();
+	json["list"][0] = SharedJson::Null;
+	json["new"] = SharedJson::Boolean(true);
+	assert_eq!(
+		json.to_value(),
+		json!({"list": [null], "name": "a", "new": true})
+	);
+}
+
+#[test]
+fn shared_values_cross_threads() {
+	fn send_sync<T: Send + Sync>() {}
+	send_sync::<SharedJson>();
+
+	let config = SharedJson::from(json!({"workers": [1, 2, 3], "name": "svc"}));
+	let handles: Vec<_> = (0..3)
+		.map(|idx| {
+			let mut config = config.clone();
+			std::thread::spawn(move || {
+				config["workers"][idx] = SharedJson::Null;
+				config.to_string()
+			})
+		})
+		.collect();
+	for handle in handles {
+		assert_eq!(handle.join().unwrap().matches("null").count(), 1);
+	}
+	assert_eq!(config["workers"].as_list().map(Vec::len), Some(3));
+
+	let input = "[".repeat(100000) + &"]".repeat(100000);
+	let deep: SharedJson = input.parse().unwrap();
+	let copy = deep.clone();
+	drop(deep);
+	assert_eq!(copy.to_string(), input);
+}
+
+// Deterministic xorshift bytes to drive the generator
+fn random_bytes(seed: u64, len: usize) -> Vec<u8> {
+	let mut state = seed;
+	(0..len)
+		.map(|_| {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state as u8
+		})
+		.collect()
+}
+
+#[test]
+fn generated_values_round_trip() {
+	assert_eq!(Generator::new(&[]).value(), JsonValue::Null);
+	assert_eq!(
+		Generator::new(&[6, 2, 0, 1, 1]).value(),
+		json!([null, true])
+	);
+	let deep = Generator::new(&[6; 1000])
+		.with_max_depth(4)
+		.value()
+		.to_string();
+	assert!(deep.starts_with("[[[[null,null,") && !deep.contains("[[[[["));
+
+	for seed in 1..500 {
+		let bytes = random_bytes(seed, 2000);
+		let value = Generator::new(&bytes).value();
+		let text = value.to_string();
+		assert_eq!(text.parse::<JsonValue>().as_ref(), Ok(&value), "{text}");
+		assert_eq!(
+			format!("{value:?}").parse::<JsonValue>().as_ref(),
+			Ok(&value)
+		);
+		assert_eq!(JsonValue::parse_fast(&text).as_ref(), Ok(&value));
+		assert_eq!(
+			parse_borrowed(&text).map(JsonValue::from).as_ref(),
+			Ok(&value)
+		);
+		assert_eq!(
+			JsonValue::from_binary(&value.to_binary()).as_ref(),
+			Ok(&value)
+		);
+		let indexed = value.to_indexed_binary();
+		assert_eq!(
+			JsonView::new(&indexed).map(|view| view.to_value()),
+			Ok(value)
+		);
+	}
+}
+
+#[test]
+fn parsers_reject_damaged_input_without_panicking() {
+	let mut arena = JsonArena::new();
+	for seed in 1..200 {
+		let bytes = random_bytes(seed, 500);
+		let text = Generator::new(&bytes).value().to_string();
+		let damage = random_bytes(seed + 1000, 4);
+		let mut mutated = text.clone().into_bytes();
+		let at = damage[0] as usize * mutated.len() / 256;
+		mutated[at] = b"{}[]\",:0e-\\u"[damage[1] as usize % 12];
+		let cut = damage[2] as usize * text.len() / 256;
+		let inputs = [
+			String::from_utf8_lossy(&mutated).into_owned(),
+			text.get(..cut).unwrap_or("").to_string(),
+		];
+		for input in &inputs {
+			let expected = input.parse::<JsonValue>().ok();
+			let _ = JsonValue::parse_fast(input);
+			assert_eq!(parse_borrowed(input).ok().map(JsonValue::from), expected);
+			let _ = arena.parse(input).map(|value| value.into_owned());
+			let _ = JsonValue::from_binary(input.as_bytes());
+			let _ = JsonView::new(input.as_bytes()).map(|view| view.to_value());
+		}
+	}
+}
@@ -0,0 +1,165 @@
+// A const version of the parser's grammar, so that json_str! can reject invalid literals at
+// compile time. It never accepts anything the parser would reject.
+#[doc(hidden)]
+pub const fn validate_literal(input: &str) -> Result<(), &'static str> {
+	#[derive(Clone, Copy)]
+	enum Expecting {
+		CommaOrBrace,
+		CommaOrBracket,
+		Key,
+		KeyOrBrace,
+		Value,
+		ValueOrBracket,
+	}
+	use Expecting::*;
+
+	let bytes = input.as_bytes();
+	// Bit n is set if the container at depth n is an object
+	let mut objects = 0_u128;
+	let mut depth = 0;
+	let mut expect = Value;
+	let mut i = 0;
+
+	loop {
+		if i >= bytes.len() {
+			return Err("unexpected end of input");
+		}
+		let c = bytes[i];
+		i += 1;
+
+		match (c, expect) {
+			(b' ' | b'\t' | b'\n' | b'\r', _) if depth > 0 => continue,
+			(b'{' | b'[', Value | ValueOrBracket) => {
+				if depth == u128::BITS {
+					return Err("nesting too deep");
+				}
+				objects = objects & !(1 << depth) | ((c == b'{') as u128) << depth;
+				depth += 1;
+				expect = if c == b'{' {
+					KeyOrBrace
+				} else {
+					ValueOrBracket
+				};
+				continue;
+			}
+			(b'}', CommaOrBrace | KeyOrBrace) | (b']', CommaOrBracket | ValueOrBracket) => {
+				depth -= 1
+			}
+			(b',', CommaOrBracket) => {
+				expect = Value;
+				continue;
+			}
+			(b',', CommaOrBrace) => {
+				expect = Key;
+				continue;
+			}
+			(b'"', Value | ValueOrBracket | Key | KeyOrBrace) => {
+				loop {
+					if i >= bytes.len() {
+						return Err("missing end quote");
+					}
+					match bytes[i] {
+						b'"' => break,
+						b'\\' if i + 1 >= bytes.len() => return Err("missing escape sequence"),
+						b'\\' => match bytes[i + 1] {
+							b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => i += 1,
+							b'u' => {
+								if i + 6 > bytes.len() {
+									return Err("invalid hex string");
+								}
+								let mut j = i + 2;
+								while j < i + 6 {
+									if !bytes[j].is_ascii_hexdigit() {
+										return Err("invalid hex string");
+									}
+									j += 1;
+								}
+								i += 5;
+							}
+							_ => return Err("invalid escape sequence"),
+						},
+						c if c.is_ascii_control() => return Err("illegal control character"),
+						_ => {}
+					}
+					i += 1;
+				}
+				i += 1;
+
+				if matches!(expect, Key | KeyOrBrace) {
+					while i < bytes.len() && matches!(bytes[i], b' ' | b'\t' | b'\n' | b'\r') {
+						i += 1;
+					}
+					if i >= bytes.len() || bytes[i] != b':' {
+						return Err("missing colon");
+					}
+					i += 1;
+					expect = Value;
+					continue;
+				}
+			}
+			(b'-' | b'0'..=b'9', Value | ValueOrBracket) => {
+				if c == b'-' {
+					if i >= bytes.len() || !bytes[i].is_ascii_digit() {
+						return Err("invalid number");
+					}
+					i += 1;
+				}
+				if bytes[i - 1] == b'0' && i < bytes.len() && bytes[i].is_ascii_digit() {
+					return Err("illegal leading zero");
+				}
+
+				let mut seen_dot = false;
+				while i < bytes.len() {
+					match bytes[i] {
+						b'0'..=b'9' => {}
+						b'.' if !seen_dot => seen_dot = true,
+						b'e' | b'E' => {
+							i += 1;
+							if i >= bytes.len() || !matches!(bytes[i], b'0'..=b'9' | b'-' | b'+') {
+								return Err("invalid number");
+							}
+							i += 1;
+							while i < bytes.len() && bytes[i].is_ascii_digit() {
+								i += 1;
+							}
+							break;
+						}
+						_ => break,
+					}
+					i += 1;
+				}
+			}
+			(b't', Value | ValueOrBracket) if starts_with(bytes, i - 1, b"true") => i += 3,
+			(b'f', Value | ValueOrBracket) if starts_with(bytes, i - 1, b"false") => i += 4,
+			(b'n', Value | ValueOrBracket) if starts_with(bytes, i - 1, b"null") => i += 3,
+			_ => return Err("unexpected character"),
+		}
+
+		if depth == 0 {
+			return if i == bytes.len() {
+				Ok(())
+			} else {
+				Err("unexpected character")
+			};
+		}
+		expect = if objects & (1 << (depth - 1)) != 0 {
+			CommaOrBrace
+		} else {
+			CommaOrBracket
+		};
+	}
+}
+
+const fn starts_with(bytes: &[u8], start: usize, prefix: &[u8]) -> bool {
+	if start + prefix.len() > bytes.len() {
+		return false;
+	}
+	let mut i = 0;
+	while i < prefix.len() {
+		if bytes[start + i] != prefix[i] {
+			return false;
+		}
+		i += 1;
+	}
+	true
+}
@@ -0,0 +1,336 @@
+use crate::binary::{FALSE, LIST, NULL, NUMBER, OBJECT, STRING, TRUE};
+use crate::{Error, JsonKind, JsonValue, ParseError};
+use std::fmt::{self, Debug, Formatter};
+
+const MAGIC: &[u8; 4] = b"MJSI";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 5;
+
+// Lists are followed by a table of element offsets, and objects by a table of (key offset, value
+// offset) pairs sorted by key. All offsets and lengths are little-endian u64s from the start of
+// the buffer.
+impl JsonValue {
+	pub fn to_indexed_binary(&self) -> Vec<u8> {
+		let mut out = MAGIC.to_vec();
+		out.push(VERSION);
+
+		// Each value is written to the end of the buffer, then its offset is patched into the
+		// slot its parent reserved for it
+		let mut stack = vec![(self, None)];
+		while let Some((value, slot)) = stack.pop() {
+			if let Some(slot) = slot {
+				let offset = out.len() as u64;
+				out[slot..slot + 8].copy_from_slice(&offset.to_le_bytes());
+			}
+
+			match value {
+				JsonValue::Null => out.push(NULL),
+				JsonValue::Boolean(false) => out.push(FALSE),
+				JsonValue::Boolean(true) => out.push(TRUE),
+				JsonValue::Number(num) => {
+					out.push(NUMBER);
+					out.extend(f64::from(num).to_le_bytes());
+				}
+				JsonValue::String(s) => {
+					out.push(STRING);
+					write_str(&mut out, s);
+				}
+				JsonValue::List(ls) => {
+					out.push(LIST);
+					out.extend((ls.len() as u64).to_le_bytes());
+					let table = out.len();
+					out.resize(table + 8 * ls.len(), 0);
+					stack.extend(
+						ls.iter()
+							.enumerate()
+							.rev()
+							.map(|(idx, val)| (val, Some(table + 8 * idx))),
+					);
+				}
+				JsonValue::Object(obj) => {
+					out.push(OBJECT);
+					out.extend((obj.len() as u64).to_le_bytes());
+					let mut entries: Vec<_> = obj.iter().collect();
+					entries.sort_unstable_by_key(|(key, _)| key.as_str());
+					let table = out.len();
+					out.resize(table + 16 * entries.len(), 0);
+					for (idx, (key, _)) in entries.iter().enumerate() {
+						let offset = out.len() as u64;
+						out[table + 16 * idx..][..8].copy_from_slice(&offset.to_le_bytes());
+						write_str(&mut out, key);
+					}
+					stack.extend(
+						entries
+							.into_iter()
+							.enumerate()
+							.rev()
+							.map(|(idx, (_, val))| (val, Some(table + 16 * idx + 8))),
+					);
+				}
+			}
+		}
+		out
+	}
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+	out.extend((s.len() as u64).to_le_bytes());
+	out.extend_from_slice(s.as_bytes());
+}
+
+// A read-only view of a value in the format written by to_indexed_binary. Nothing is decoded up
+// front, so looking up a path only touches the bytes along it. Malformed data reads as missing
+// rather than panicking.
+#[derive(Clone, Copy)]
+pub struct JsonView<'a> {
+	bytes: &'a [u8],
+	offset: usize,
+}
+
+impl<'a> JsonView<'a> {
+	pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+		let error = |message: &str, offset| ParseError {
+			kind: message.into(),
+			offset,
+		};
+		if bytes.get(..4) != Some(MAGIC) {
+			Err(error("not a microjson indexed binary", 0))?
+		}
+		match bytes.get(4) {
+			Some(&VERSION) => {}
+			Some(version) => Err(error(
+				&format!("unsupported indexed binary version {version}"),
+				4,
+			))?,
+			None => Err(error("unexpected end of input", 4))?,
+		}
+		JsonView::at(bytes, HEADER_LEN)
+			.ok_or_else(|| error("invalid root value", HEADER_LEN).into())
+	}
+
+	fn at(bytes: &'a [u8], offset: usize) -> Option<Self> {
+		matches!(bytes.get(offset)?, NULL..=OBJECT).then_some(JsonView { bytes, offset })
+	}
+
+	fn tag(&self) -> u8 {
+		self.bytes[self.offset]
+	}
+
+	fn read_u64(&self, at: usize) -> Option<usize> {
+		let bytes = self.bytes.get(at..at.checked_add(8)?)?;
+		usize::try_from(u64::from_le_bytes(bytes.try_into().unwrap())).ok()
+	}
+
+	fn read_str(&self, at: usize) -> Option<&'a str> {
+		let len = self.read_u64(at)?;
+		let start = at + 8;
+		std::str::from_utf8(self.bytes.get(start..start.checked_add(len)?)?).ok()
+	}
+
+	// The number of entries in a list or object, or 0 if the table wouldn't fit in the buffer
+	fn count(&self, tag: u8, entry_len: usize) -> usize {
+		if self.tag() != tag {
+			return 0;
+		}
+		let count = self.read_u64(self.offset + 1).unwrap_or(0);
+		let available = self.bytes.len().saturating_sub(self.offset + 9);
+		if count > available / entry_len {
+			0
+		} else {
+			count
+		}
+	}
+
+	fn list_item(&self, idx: usize) -> Option<Self> {
+		JsonView::at(self.bytes, self.read_u64(self.offset + 9 + 8 * idx)?)
+	}
+
+	fn object_entry(&self, idx: usize) -> Option<(&'a [u8], usize)> {
+		let entry = self.offset + 9 + 16 * idx;
+		let key_offset = self.read_u64(entry)?;
+		let len = self.read_u64(key_offset)?;
+		let start = key_offset.checked_add(8)?;
+		let key = self.bytes.get(start..start.checked_add(len)?)?;
+		Some((key, self.read_u64(entry + 8)?))
+	}
+
+	pub fn kind(&self) -> JsonKind {
+		match self.tag() {
+			NULL => JsonKind::Null,
+			FALSE | TRUE => JsonKind::Boolean,
+			NUMBER => JsonKind::Number,
+			STRING => JsonKind::String,
+			LIST => JsonKind::List,
+			_ => JsonKind::Object,
+		}
+	}
+
+	pub fn is_null(&self) -> bool {
+		self.kind() == JsonKind::Null
+	}
+
+	pub fn is_bool(&self) -> bool {
+		self.kind() == JsonKind::Boolean
+	}
+
+	pub fn is_number(&self) -> bool {
+		self.kind() == JsonKind::Number
+	}
+
+	pub fn is_string(&self) -> bool {
+		self.kind() == JsonKind::String
+	}
+
+	pub fn is_list(&self) -> bool {
+		self.kind() == JsonKind::List
+	}
+
+	pub fn is_object(&self) -> bool {
+		self.kind() == JsonKind::Object
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		match self.tag() {
+			FALSE => Some(false),
+			TRUE => Some(true),
+			_ => None,
+		}
+	}
+
+	pub fn as_f64(&self) -> Option<f64> {
+		let bytes = self.bytes.get(self.offset + 1..self.offset + 9)?;
+		let num = f64::from_le_bytes(bytes.try_into().unwrap());
+		(self.tag() == NUMBER && num.is_finite()).then_some(num)
+	}
+
+	pub fn as_str(&self) -> Option<&'a str> {
+		(self.tag() == STRING).then(|| self.read_str(self.offset + 1))?
+	}
+
+	// The number of entries in a list or object, and 0 for anything else
+	pub fn len(&self) -> usize {
+		match self.tag() {
+			LIST => self.count(LIST, 8),
+			OBJECT => self.count(OBJECT, 16),
+			_ => 0,
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn index(&self, idx: usize) -> Option<Self> {
+		(idx < self.count(LIST, 8)).then(|| self.list_item(idx))?
+	}
+
+	// Binary search over the sorted key table
+	pub fn get(&self, key: &str) -> Option<Self> {
+		let (mut low, mut high) = (0, self.count(OBJECT, 16));
+		while low < high {
+			let mid = low + (high - low) / 2;
+			let (mid_key, value) = self.object_entry(mid)?;
+			match mid_key.cmp(key.as_bytes()) {
+				std::cmp::Ordering::Less => low = mid + 1,
+				std::cmp::Ordering::Greater => high = mid,
+				std::cmp::Ordering::Equal => return JsonView::at(self.bytes, value),
+			}
+		}
+		None
+	}
+
+	// The values of a list, or nothing for anything else
+	pub fn iter(&self) -> impl Iterator<Item = JsonView<'a>> + use<'a> {
+		let view = *self;
+		(0..self.count(LIST, 8)).filter_map(move |idx| view.list_item(idx))
+	}
+
+	// The entries of an object in key order, or nothing for anything else
+	pub fn entries(&self) -> impl Iterator<Item = (&'a str, JsonView<'a>)> + use<'a> {
+		let view = *self;
+		(0..self.count(OBJECT, 16)).filter_map(move |idx| {
+			let (key, value) = view.object_entry(idx)?;
+			Some((
+				std::str::from_utf8(key).ok()?,
+				JsonView::at(view.bytes, value)?,
+			))
+		})
+	}
+
+	// Decodes this value and everything below it
+	pub fn to_value(&self) -> JsonValue {
+		struct Frame<'a> {
+			value: JsonValue,
+			// In reverse, so the next child is popped off the end
+			children: Vec<(&'a str, JsonView<'a>)>,
+			key: &'a str,
+		}
+
+		let mut stack: Vec<Frame> = vec![];
+		let mut next = *self;
+
+		loop {
+			let mut value = match next.kind() {
+				JsonKind::Null => Some(JsonValue::Null),
+				JsonKind::Boolean => next.as_bool().map(JsonValue::Boolean),
+				JsonKind::Number => Some(next.as_f64().map_or(JsonValue::Null, JsonValue::from)),
+				JsonKind::String => Some(next.as_str().map_or(JsonValue::Null, JsonValue::from)),
+				JsonKind::List => {
+					let mut children: Vec<_> = next.iter().map(|val| ("", val)).collect();
+					children.reverse();
+					stack.push(Frame {
+						value: JsonValue::list(),
+						children,
+						key: "",
+					});
+					None
+				}
+				JsonKind::Object => {
+					let mut children: Vec<_> = next.entries().collect();
+					children.reverse();
+					stack.push(Frame {
+						value: JsonValue::object(),
+						children,
+						key: "",
+					});
+					None
+				}
+			};
+
+			loop {
+				let Some(Frame {
+					value: parent,
+					children,
+					key,
+				}) = stack.last_mut()
+				else {
+					return value.unwrap();
+				};
+				match (parent, value.take()) {
+					(JsonValue::List(ls), Some(val)) => ls.push(val),
+					(JsonValue::Object(obj), Some(val)) => {
+						obj.insert(key.to_string(), val);
+					}
+					_ => {}
+				}
+				match children.pop() {
+					// Values are written in the order they're visited here, so a child at or before
+					// the last value read can only come from a cycle or a shared subtree
+					Some((_, child)) if child.offset <= next.offset => continue,
+					Some((child_key, child)) => {
+						*key = child_key;
+						next = child;
+						break;
+					}
+					None => value = stack.pop().map(|frame| frame.value),
+				}
+			}
+		}
+	}
+}
+
+impl Debug for JsonView<'_> {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{:?}", self.to_value())
+	}
+}
@@ -0,0 +1,220 @@
+use crate::JsonValue;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::rc::Rc;
+
+// An RFC 6901 pointer. Children share their parent's path, so building one per node of a
+// traversal is cheap no matter how deep the tree is.
+#[derive(Clone, Default)]
+pub struct JsonPointer(Option<Rc<Segment>>);
+
+struct Segment {
+	parent: JsonPointer,
+	token: Token,
+}
+
+enum Token {
+	Key(String),
+	Index(usize),
+}
+
+impl JsonPointer {
+	pub fn root() -> Self {
+		JsonPointer(None)
+	}
+
+	pub fn key(&self, key: impl Into<String>) -> Self {
+		self.child(Token::Key(key.into()))
+	}
+
+	pub fn index(&self, idx: usize) -> Self {
+		self.child(Token::Index(idx))
+	}
+
+	fn child(&self, token: Token) -> Self {
+		JsonPointer(Some(Rc::new(Segment {
+			parent: self.clone(),
+			token,
+		})))
+	}
+
+	pub fn parent(&self) -> Option<&JsonPointer> {
+		self.0.as_ref().map(|segment| &segment.parent)
+	}
+
+	pub fn is_root(&self) -> bool {
+		self.0.is_none()
+	}
+
+	pub fn depth(&self) -> usize {
+		self.segments().count()
+	}
+
+	fn segments(&self) -> impl Iterator<Item = &Segment> {
+		let mut next = self.0.as_deref();
+		std::iter::from_fn(move || {
+			let segment = next?;
+			next = segment.parent.0.as_deref();
+			Some(segment)
+		})
+	}
+}
+
+impl Drop for JsonPointer {
+	fn drop(&mut self) {
+		let mut next = self.0.take();
+		while let Some(segment) = next {
+			next = Rc::try_unwrap(segment)
+				.ok()
+				.and_then(|mut seg| seg.parent.0.take());
+		}
+	}
+}
+
+impl PartialEq for JsonPointer {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.depth() == rhs.depth()
+			&& self
+				.segments()
+				.zip(rhs.segments())
+				.all(|(l, r)| match (&l.token, &r.token) {
+					(Token::Key(l), Token::Key(r)) => l == r,
+					(Token::Index(l), Token::Index(r)) => l == r,
+					_ => false,
+				})
+	}
+}
+
+impl Eq for JsonPointer {}
+
+impl Display for JsonPointer {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		let mut segments: Vec<_> = self.segments().collect();
+		segments.reverse();
+		for segment in segments {
+			match &segment.token {
+				Token::Key(key) => write!(f, "/{}", key.replace('~', "~0").replace('/', "~1"))?,
+				Token::Index(idx) => write!(f, "/{idx}")?,
+			}
+		}
+		Ok(())
+	}
+}
+
+impl Debug for JsonPointer {
+	fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+		write!(f, "{:?}", self.to_string())
+	}
+}
+
+pub struct Walk<'a> {
+	stack: Vec<(JsonPointer, &'a JsonValue)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+	type Item = (JsonPointer, &'a JsonValue);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (pointer, value) = self.stack.pop()?;
+		match value {
+			JsonValue::List(ls) => self.stack.extend(
+				ls.iter()
+					.enumerate()
+					.rev()
+					.map(|(idx, val)| (pointer.index(idx), val)),
+			),
+			JsonValue::Object(obj) => self
+				.stack
+				.extend(obj.iter().map(|(key, val)| (pointer.key(key), val))),
+			_ => {}
+		}
+		Some((pointer, value))
+	}
+}
+
+// Not an Iterator, since each value is only borrowed until the next call, when its children are
+// listed. Loop over it with while let instead.
+pub struct WalkMut<'a> {
+	stack: Vec<(JsonPointer, &'a mut JsonValue)>,
+	current: Option<(JsonPointer, &'a mut JsonValue)>,
+}
+
+impl WalkMut<'_> {
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> Option<(&JsonPointer, &mut JsonValue)> {
+		if let Some((pointer, value)) = self.current.take() {
+			match value {
+				JsonValue::List(ls) => self.stack.extend(
+					ls.iter_mut()
+						.enumerate()
+						.rev()
+						.map(|(idx, val)| (pointer.index(idx), val)),
+				),
+				// Going through the Option skips creating the maps of empty objects
+				JsonValue::Object(obj) => self.stack.extend(
+					obj.0
+						.iter_mut()
+						.flat_map(|obj| obj.iter_mut())
+						.map(|(key, val)| (pointer.key(key), val)),
+				),
+				_ => {}
+			}
+		}
+		let (pointer, value) = self.current.insert(self.stack.pop()?);
+		Some((pointer, value))
+	}
+}
+
+pub trait Visitor {
+	// Return false to skip the value's children. leave is still called either way.
+	fn enter(&mut self, _pointer: &JsonPointer, _value: &JsonValue) -> bool {
+		true
+	}
+
+	fn leave(&mut self, _pointer: &JsonPointer, _value: &JsonValue) {}
+}
+
+impl JsonValue {
+	// Pre-order traversal of every value in the tree, including this one
+	pub fn walk(&self) -> Walk<'_> {
+		Walk {
+			stack: vec![(JsonPointer::root(), self)],
+		}
+	}
+
+	// Pre-order like walk, but hands out each value mutably before its children are listed, so
+	// replacing a value also changes what gets walked next
+	pub fn walk_mut(&mut self) -> WalkMut<'_> {
+		WalkMut {
+			stack: vec![(JsonPointer::root(), self)],
+			current: None,
+		}
+	}
+
+	pub fn visit(&self, visitor: &mut impl Visitor) {
+		let mut stack = vec![(true, JsonPointer::root(), self)];
+		while let Some((entering, pointer, value)) = stack.pop() {
+			if !entering {
+				visitor.leave(&pointer, value);
+				continue;
+			}
+
+			let descend = visitor.enter(&pointer, value);
+			stack.push((false, pointer.clone(), value));
+			if !descend {
+				continue;
+			}
+			match value {
+				JsonValue::List(ls) => stack.extend(
+					ls.iter()
+						.enumerate()
+						.rev()
+						.map(|(idx, val)| (true, pointer.index(idx), val)),
+				),
+				JsonValue::Object(obj) => {
+					stack.extend(obj.iter().map(|(key, val)| (true, pointer.key(key), val)))
+				}
+				_ => {}
+			}
+		}
+	}
+}